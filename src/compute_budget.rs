@@ -1,3 +1,23 @@
+/// Max instruction stack depth. This is the maximum nesting of instructions that can happen during
+/// a transaction.
+pub const MAX_INSTRUCTION_STACK_DEPTH: usize = 5;
+
+/// Max call depth. This is the maximum nesting of SBF to SBF call that can happen within a program.
+pub const MAX_CALL_DEPTH: usize = 64;
+
+/// The size of one SBF stack frame.
+pub const STACK_FRAME_SIZE: usize = 4096;
+
+/// Maximum number of compute units a transaction may request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Length of the heap memory region used for program heap.
+pub const HEAP_LENGTH: usize = 32 * 1024;
+
+/// Roughly 0.5us/page, where page is 32K; given roughly 15CU/us, the
+/// default heap page cost = 0.5 * 15 ~= 8CU/page
+pub const DEFAULT_HEAP_COST: u64 = 8;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ComputeBudget {
     /// Number of compute units that a transaction or individual instruction is
@@ -107,4 +127,246 @@ pub struct ComputeBudget {
     pub alt_bn128_g2_compress: u64,
     /// Number of compute units consumed to call alt_bn128_g2_decompress.
     pub alt_bn128_g2_decompress: u64,
-}
\ No newline at end of file
+}
+
+impl Default for ComputeBudget {
+    fn default() -> Self {
+        Self::new(MAX_COMPUTE_UNIT_LIMIT as u64)
+    }
+}
+
+impl ComputeBudget {
+    /// Budget with the cost table of the latest supported Agave release
+    pub fn new(compute_unit_limit: u64) -> Self {
+        Self::agave_v2_2(compute_unit_limit)
+    }
+
+    /// Cost table of Agave 2.2
+    pub fn agave_v2_2(compute_unit_limit: u64) -> Self {
+        ComputeBudget {
+            compute_unit_limit,
+            log_64_units: 100,
+            create_program_address_units: 1500,
+            invoke_units: 1000,
+            max_instruction_stack_depth: MAX_INSTRUCTION_STACK_DEPTH,
+            max_instruction_trace_length: 64,
+            sha256_base_cost: 85,
+            sha256_byte_cost: 1,
+            sha256_max_slices: 20_000,
+            max_call_depth: MAX_CALL_DEPTH,
+            stack_frame_size: STACK_FRAME_SIZE,
+            log_pubkey_units: 100,
+            max_cpi_instruction_size: 1280, // IPv6 Min MTU size
+            cpi_bytes_per_unit: 250,        // ~50MB at 200,000 units
+            sysvar_base_cost: 100,
+            secp256k1_recover_cost: 25_000,
+            syscall_base_cost: 100,
+            curve25519_edwards_validate_point_cost: 159,
+            curve25519_edwards_add_cost: 473,
+            curve25519_edwards_subtract_cost: 475,
+            curve25519_edwards_multiply_cost: 2_177,
+            curve25519_edwards_msm_base_cost: 2_273,
+            curve25519_edwards_msm_incremental_cost: 758,
+            curve25519_ristretto_validate_point_cost: 169,
+            curve25519_ristretto_add_cost: 521,
+            curve25519_ristretto_subtract_cost: 519,
+            curve25519_ristretto_multiply_cost: 2_208,
+            curve25519_ristretto_msm_base_cost: 2303,
+            curve25519_ristretto_msm_incremental_cost: 788,
+            heap_size: HEAP_LENGTH as u32,
+            heap_cost: DEFAULT_HEAP_COST,
+            mem_op_base_cost: 10,
+            alt_bn128_addition_cost: 334,
+            alt_bn128_multiplication_cost: 3_840,
+            alt_bn128_pairing_one_pair_cost_first: 36_364,
+            alt_bn128_pairing_one_pair_cost_other: 12_121,
+            big_modular_exponentiation_base_cost: 190,
+            big_modular_exponentiation_cost_divisor: 2,
+            poseidon_cost_coefficient_a: 61,
+            poseidon_cost_coefficient_c: 542,
+            get_remaining_compute_units_cost: 100,
+            alt_bn128_g1_compress: 30,
+            alt_bn128_g1_decompress: 398,
+            alt_bn128_g2_compress: 86,
+            alt_bn128_g2_decompress: 13610,
+        }
+    }
+
+    /// Cost table of Agave 1.18
+    ///
+    /// Only `sol_big_mod_exp` is priced differently from 2.2: 1.18 charged
+    /// `syscall_base_cost + input_len^2 / 33`, with no additional base cost.
+    pub fn agave_v1_18(compute_unit_limit: u64) -> Self {
+        ComputeBudget {
+            big_modular_exponentiation_base_cost: 0,
+            big_modular_exponentiation_cost_divisor: 33,
+            ..Self::agave_v2_2(compute_unit_limit)
+        }
+    }
+
+    /// Returns cost of the Poseidon hash function for the given number of
+    /// inputs is determined by the following quadratic function:
+    ///
+    /// 61*n^2 + 542
+    ///
+    /// Which aproximates the results of benchmarks of light-posiedon
+    /// library[0]. These results assume 1 CU per 33 ns.
+    ///
+    /// [0] https://github.com/Lightprotocol/light-poseidon#performance
+    pub fn poseidon_cost(&self, nr_inputs: u64) -> Option<u64> {
+        let squared_inputs = nr_inputs.checked_pow(2)?;
+        let mul_result = self
+            .poseidon_cost_coefficient_a
+            .checked_mul(squared_inputs)?;
+        let final_result = mul_result.checked_add(self.poseidon_cost_coefficient_c)?;
+
+        Some(final_result)
+    }
+
+    /// Cost of a multiscalar multiplication of `nr_points` edwards points:
+    /// `msm_base_cost + (nr_points - 1) * msm_incremental_cost`
+    pub fn edwards_msm_cost(&self, nr_points: u64) -> Option<u64> {
+        self.curve25519_edwards_msm_incremental_cost
+            .checked_mul(nr_points.saturating_sub(1))?
+            .checked_add(self.curve25519_edwards_msm_base_cost)
+    }
+
+    /// Cost of a multiscalar multiplication of `nr_points` ristretto points:
+    /// `msm_base_cost + (nr_points - 1) * msm_incremental_cost`
+    pub fn ristretto_msm_cost(&self, nr_points: u64) -> Option<u64> {
+        self.curve25519_ristretto_msm_incremental_cost
+            .checked_mul(nr_points.saturating_sub(1))?
+            .checked_add(self.curve25519_ristretto_msm_base_cost)
+    }
+
+    /// Cost of an alt_bn128 pairing over `nr_pairs` pairs:
+    /// `one_pair_cost_first + (nr_pairs - 1) * one_pair_cost_other`
+    pub fn alt_bn128_pairing_cost(&self, nr_pairs: u64) -> Option<u64> {
+        self.alt_bn128_pairing_one_pair_cost_other
+            .checked_mul(nr_pairs.saturating_sub(1))?
+            .checked_add(self.alt_bn128_pairing_one_pair_cost_first)
+    }
+
+    /// Cost of a big integer modular exponentiation, where `input_len` is the largest
+    /// of the base, exponent and modulus lengths.
+    ///
+    /// As in Agave the length is squared and charged on top of the syscall base cost:
+    /// `syscall_base_cost + input_len^2 / cost_divisor + base_cost`.
+    pub fn big_modular_exponentiation_cost(&self, input_len: u64) -> Option<u64> {
+        input_len
+            .checked_mul(input_len)?
+            .checked_div(self.big_modular_exponentiation_cost_divisor)?
+            .checked_add(self.big_modular_exponentiation_base_cost)?
+            .checked_add(self.syscall_base_cost)
+    }
+
+    /// Cost of hashing one slice of `len` bytes with SHA256, Keccak256 or Blake3
+    pub fn sha256_slice_cost(&self, len: u64) -> Option<u64> {
+        Some(
+            self.sha256_byte_cost
+                .checked_mul(len / 2)?
+                .max(self.mem_op_base_cost),
+        )
+    }
+
+    /// Cost of a memory operation over `len` bytes
+    pub fn mem_op_cost(&self, len: u64) -> Option<u64> {
+        Some(
+            len.checked_div(self.cpi_bytes_per_unit)?
+                .max(self.mem_op_base_cost),
+        )
+    }
+
+    /// Cost of a heap of `heap_size` bytes: `heap_cost` for every 32 KiB page above
+    /// the first one
+    pub fn heap_cost(&self, heap_size: u32) -> Option<u64> {
+        const KIBIBYTE: u64 = 1024;
+        const PAGE_SIZE_KB: u64 = 32;
+        let page_size = PAGE_SIZE_KB.checked_mul(KIBIBYTE)?;
+        u64::from(heap_size)
+            .checked_add(page_size.checked_sub(1)?)?
+            .checked_div(page_size)?
+            .saturating_sub(1)
+            .checked_mul(self.heap_cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_big_mod_exp_presets() {
+        let v2_2 = ComputeBudget::agave_v2_2(200_000);
+        let v1_18 = ComputeBudget::agave_v1_18(200_000);
+        for input_len in [0, 1, 32, 255, 512] {
+            // Agave 2.2: `0.5 * input_len^2 + 190` on top of the syscall base cost
+            assert_eq!(
+                v2_2.big_modular_exponentiation_cost(input_len),
+                Some(100 + input_len * input_len / 2 + 190)
+            );
+            // Agave 1.18: `input_len^2 / 33` on top of the syscall base cost
+            assert_eq!(
+                v1_18.big_modular_exponentiation_cost(input_len),
+                Some(100 + input_len * input_len / 33)
+            );
+        }
+        assert_eq!(v2_2.big_modular_exponentiation_cost(512), Some(131_362));
+        assert_eq!(v1_18.big_modular_exponentiation_cost(512), Some(8_043));
+
+        // Nothing else differs between the two releases
+        assert_eq!(
+            ComputeBudget {
+                big_modular_exponentiation_base_cost: 190,
+                big_modular_exponentiation_cost_divisor: 2,
+                ..v1_18
+            },
+            v2_2
+        );
+        assert_eq!(ComputeBudget::default(), ComputeBudget::new(1_400_000));
+    }
+
+    #[test]
+    fn test_cost_helpers() {
+        let budget = ComputeBudget::default();
+        assert_eq!(budget.poseidon_cost(1), Some(603));
+        assert_eq!(budget.poseidon_cost(12), Some(9_326));
+        assert_eq!(budget.edwards_msm_cost(0), Some(2_273));
+        assert_eq!(budget.edwards_msm_cost(1), Some(2_273));
+        assert_eq!(budget.edwards_msm_cost(2), Some(3_031));
+        assert_eq!(budget.ristretto_msm_cost(3), Some(3_879));
+        assert_eq!(budget.alt_bn128_pairing_cost(1), Some(36_364));
+        assert_eq!(budget.alt_bn128_pairing_cost(2), Some(48_485));
+        assert_eq!(budget.sha256_slice_cost(10), Some(10));
+        assert_eq!(budget.sha256_slice_cost(101), Some(50));
+        assert_eq!(budget.mem_op_cost(100), Some(10));
+        assert_eq!(budget.mem_op_cost(250 * 20), Some(20));
+        assert_eq!(budget.heap_cost(32 * 1024), Some(0));
+        assert_eq!(budget.heap_cost(32 * 1024 + 1), Some(8));
+        assert_eq!(budget.heap_cost(256 * 1024), Some(56));
+    }
+
+    #[test]
+    fn test_cost_helpers_overflow() {
+        let budget = ComputeBudget::default();
+        assert_eq!(budget.poseidon_cost(u64::MAX), None);
+        assert_eq!(budget.edwards_msm_cost(u64::MAX), None);
+        assert_eq!(budget.ristretto_msm_cost(u64::MAX), None);
+        assert_eq!(budget.alt_bn128_pairing_cost(u64::MAX), None);
+        assert_eq!(budget.big_modular_exponentiation_cost(u64::MAX), None);
+
+        let budget = ComputeBudget {
+            big_modular_exponentiation_cost_divisor: 0,
+            cpi_bytes_per_unit: 0,
+            sha256_byte_cost: 3,
+            heap_cost: u64::MAX,
+            ..ComputeBudget::default()
+        };
+        assert_eq!(budget.big_modular_exponentiation_cost(512), None);
+        assert_eq!(budget.mem_op_cost(100), None);
+        assert_eq!(budget.sha256_slice_cost(u64::MAX), None);
+        assert_eq!(budget.heap_cost(32 * 1024), Some(0));
+        assert_eq!(budget.heap_cost(64 * 1024), Some(u64::MAX));
+        assert_eq!(budget.heap_cost(96 * 1024), None);
+    }
+}
@@ -0,0 +1,363 @@
+use crate::{
+    transaction_context::Pubkey, ComputeBudget, InstructionError, TransactionError, HEAP_LENGTH,
+    MAX_COMPUTE_UNIT_LIMIT,
+};
+
+/// ComputeBudget111111111111111111111111111111
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey = [
+    3, 6, 70, 111, 229, 33, 23, 50, 255, 236, 173, 186, 114, 195, 155, 231, 188, 140, 229, 187,
+    197, 247, 18, 107, 44, 67, 155, 58, 64, 0, 0, 0,
+];
+
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+pub const MAX_HEAP_FRAME_BYTES: u32 = 256 * 1024;
+pub const MIN_HEAP_FRAME_BYTES: u32 = HEAP_LENGTH as u32;
+
+/// The total accounts data a transaction can load is limited to 64MiB to not break
+/// anyone in Mainnet-beta today. It can be set by set_loaded_accounts_data_size_limit instruction
+pub const MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES: u32 = 64 * 1024 * 1024;
+
+/// There are 10^6 micro-lamports in one lamport
+const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+/// Compute Budget Instructions
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ComputeBudgetInstruction {
+    Unused, // deprecated variant, reserved value.
+    /// Request a specific transaction-wide program heap region size in bytes.
+    /// The value requested must be a multiple of 1024. This new heap region
+    /// size applies to each program executed in the transaction, including all
+    /// calls to CPIs.
+    RequestHeapFrame(u32),
+    /// Set a specific compute unit limit that the transaction is allowed to consume.
+    SetComputeUnitLimit(u32),
+    /// Set a compute unit price in "micro-lamports" to pay a higher transaction
+    /// fee for higher transaction prioritization.
+    SetComputeUnitPrice(u64),
+    /// Set a specific transaction-wide account data size limit, in bytes, is allowed to load.
+    SetLoadedAccountsDataSizeLimit(u32),
+}
+
+impl ComputeBudgetInstruction {
+    /// Decodes the borsh layout (one byte tag, then a little endian value), ignoring
+    /// trailing bytes like the runtime does.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let (tag, value) = data.split_first()?;
+        match tag {
+            0 => Some(Self::Unused),
+            1 => Some(Self::RequestHeapFrame(u32::from_le_bytes(
+                value.get(..4)?.try_into().ok()?,
+            ))),
+            2 => Some(Self::SetComputeUnitLimit(u32::from_le_bytes(
+                value.get(..4)?.try_into().ok()?,
+            ))),
+            3 => Some(Self::SetComputeUnitPrice(u64::from_le_bytes(
+                value.get(..8)?.try_into().ok()?,
+            ))),
+            4 => Some(Self::SetLoadedAccountsDataSizeLimit(u32::from_le_bytes(
+                value.get(..4)?.try_into().ok()?,
+            ))),
+            _ => None,
+        }
+    }
+}
+
+/// Limits requested by the compute budget instructions of a transaction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComputeBudgetLimits {
+    pub updated_heap_bytes: u32,
+    pub compute_unit_limit: u32,
+    pub compute_unit_price: u64,
+    pub loaded_accounts_bytes: u32,
+}
+
+impl Default for ComputeBudgetLimits {
+    fn default() -> Self {
+        ComputeBudgetLimits {
+            updated_heap_bytes: MIN_HEAP_FRAME_BYTES,
+            compute_unit_limit: MAX_COMPUTE_UNIT_LIMIT,
+            compute_unit_price: 0,
+            loaded_accounts_bytes: MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES,
+        }
+    }
+}
+
+impl ComputeBudgetLimits {
+    /// Priority fee in lamports: `compute_unit_price * compute_unit_limit` micro-lamports,
+    /// rounded up
+    pub fn prioritization_fee(&self) -> u64 {
+        let micro_lamport_fee =
+            (self.compute_unit_price as u128).saturating_mul(self.compute_unit_limit as u128);
+        micro_lamport_fee
+            .saturating_add(MICRO_LAMPORTS_PER_LAMPORT.saturating_sub(1) as u128)
+            .checked_div(MICRO_LAMPORTS_PER_LAMPORT as u128)
+            .and_then(|fee| u64::try_from(fee).ok())
+            .unwrap_or(u64::MAX)
+    }
+}
+
+impl From<ComputeBudgetLimits> for ComputeBudget {
+    fn from(compute_budget_limits: ComputeBudgetLimits) -> Self {
+        ComputeBudget {
+            compute_unit_limit: u64::from(compute_budget_limits.compute_unit_limit),
+            heap_size: compute_budget_limits.updated_heap_bytes,
+            ..ComputeBudget::default()
+        }
+    }
+}
+
+/// Scans the `(program_id, instruction_data)` pairs of a transaction for compute budget
+/// instructions and derives its limits
+pub fn process_compute_budget_instructions<'a>(
+    instructions: impl Iterator<Item = (&'a Pubkey, &'a [u8])>,
+) -> Result<ComputeBudgetLimits, TransactionError> {
+    let mut num_non_compute_budget_instructions: u32 = 0;
+    let mut updated_compute_unit_limit = None;
+    let mut updated_compute_unit_price = None;
+    let mut requested_heap_size = None;
+    let mut updated_loaded_accounts_data_size_limit = None;
+
+    for (i, (program_id, instruction_data)) in instructions.enumerate() {
+        if *program_id == COMPUTE_BUDGET_PROGRAM_ID {
+            let invalid_instruction_data_error = TransactionError::InstructionError(
+                i as u8,
+                InstructionError::InvalidInstructionData,
+            );
+            let duplicate_instruction_error = TransactionError::DuplicateInstruction(i as u8);
+
+            match ComputeBudgetInstruction::decode(instruction_data) {
+                Some(ComputeBudgetInstruction::RequestHeapFrame(bytes)) => {
+                    if requested_heap_size.is_some() {
+                        return Err(duplicate_instruction_error);
+                    }
+                    if sanitize_requested_heap_size(bytes) {
+                        requested_heap_size = Some(bytes);
+                    } else {
+                        return Err(invalid_instruction_data_error);
+                    }
+                }
+                Some(ComputeBudgetInstruction::SetComputeUnitLimit(compute_unit_limit)) => {
+                    if updated_compute_unit_limit.is_some() {
+                        return Err(duplicate_instruction_error);
+                    }
+                    updated_compute_unit_limit = Some(compute_unit_limit);
+                }
+                Some(ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports)) => {
+                    if updated_compute_unit_price.is_some() {
+                        return Err(duplicate_instruction_error);
+                    }
+                    updated_compute_unit_price = Some(micro_lamports);
+                }
+                Some(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(bytes)) => {
+                    if updated_loaded_accounts_data_size_limit.is_some() {
+                        return Err(duplicate_instruction_error);
+                    }
+                    updated_loaded_accounts_data_size_limit = Some(bytes);
+                }
+                _ => return Err(invalid_instruction_data_error),
+            }
+        } else {
+            num_non_compute_budget_instructions =
+                num_non_compute_budget_instructions.saturating_add(1);
+        }
+    }
+
+    let updated_heap_bytes = requested_heap_size
+        .unwrap_or(MIN_HEAP_FRAME_BYTES) // loader's default heap_size
+        .min(MAX_HEAP_FRAME_BYTES);
+
+    let compute_unit_limit = updated_compute_unit_limit
+        .unwrap_or_else(|| {
+            num_non_compute_budget_instructions
+                .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+        })
+        .min(MAX_COMPUTE_UNIT_LIMIT);
+
+    let compute_unit_price = updated_compute_unit_price.unwrap_or(0);
+
+    let loaded_accounts_bytes = match updated_loaded_accounts_data_size_limit {
+        Some(0) => return Err(TransactionError::InvalidLoadedAccountsDataSizeLimit),
+        Some(bytes) => bytes.min(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES),
+        None => MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES,
+    };
+
+    Ok(ComputeBudgetLimits {
+        updated_heap_bytes,
+        compute_unit_limit,
+        compute_unit_price,
+        loaded_accounts_bytes,
+    })
+}
+
+fn sanitize_requested_heap_size(bytes: u32) -> bool {
+    (MIN_HEAP_FRAME_BYTES..=MAX_HEAP_FRAME_BYTES).contains(&bytes) && bytes.is_multiple_of(1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OTHER_PROGRAM_ID: Pubkey = [1; 32];
+
+    fn heap_frame(bytes: u32) -> Vec<u8> {
+        let mut data = vec![1];
+        data.extend_from_slice(&bytes.to_le_bytes());
+        data
+    }
+
+    fn compute_unit_limit(units: u32) -> Vec<u8> {
+        let mut data = vec![2];
+        data.extend_from_slice(&units.to_le_bytes());
+        data
+    }
+
+    fn compute_unit_price(micro_lamports: u64) -> Vec<u8> {
+        let mut data = vec![3];
+        data.extend_from_slice(&micro_lamports.to_le_bytes());
+        data
+    }
+
+    fn loaded_accounts_data_size_limit(bytes: u32) -> Vec<u8> {
+        let mut data = vec![4];
+        data.extend_from_slice(&bytes.to_le_bytes());
+        data
+    }
+
+    fn process(
+        instructions: &[(Pubkey, Vec<u8>)],
+    ) -> Result<ComputeBudgetLimits, TransactionError> {
+        process_compute_budget_instructions(
+            instructions
+                .iter()
+                .map(|(program_id, data)| (program_id, data.as_slice())),
+        )
+    }
+
+    #[test]
+    fn test_duplicate_instructions() {
+        for instruction in [
+            heap_frame(40 * 1024),
+            compute_unit_limit(1_000),
+            compute_unit_price(1),
+            loaded_accounts_data_size_limit(1_000),
+        ] {
+            assert_eq!(
+                process(&[
+                    (OTHER_PROGRAM_ID, vec![]),
+                    (COMPUTE_BUDGET_PROGRAM_ID, instruction.clone()),
+                    (COMPUTE_BUDGET_PROGRAM_ID, instruction),
+                ]),
+                Err(TransactionError::DuplicateInstruction(2))
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_heap_frame() {
+        for bytes in [
+            40 * 1024 + 1,
+            MIN_HEAP_FRAME_BYTES - 1024,
+            MAX_HEAP_FRAME_BYTES + 1024,
+        ] {
+            assert_eq!(
+                process(&[
+                    (OTHER_PROGRAM_ID, vec![]),
+                    (COMPUTE_BUDGET_PROGRAM_ID, heap_frame(bytes)),
+                ]),
+                Err(TransactionError::InstructionError(
+                    1,
+                    InstructionError::InvalidInstructionData
+                ))
+            );
+        }
+        assert_eq!(
+            process(&[(COMPUTE_BUDGET_PROGRAM_ID, heap_frame(MAX_HEAP_FRAME_BYTES))])
+                .unwrap()
+                .updated_heap_bytes,
+            MAX_HEAP_FRAME_BYTES
+        );
+    }
+
+    #[test]
+    fn test_invalid_instruction_data() {
+        assert_eq!(
+            process(&[(COMPUTE_BUDGET_PROGRAM_ID, vec![5, 0, 0, 0, 0])]),
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::InvalidInstructionData
+            ))
+        );
+        assert_eq!(
+            process(&[(COMPUTE_BUDGET_PROGRAM_ID, vec![2, 0, 0])]),
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::InvalidInstructionData
+            ))
+        );
+    }
+
+    #[test]
+    fn test_zero_loaded_accounts_data_size_limit() {
+        assert_eq!(
+            process(&[(
+                COMPUTE_BUDGET_PROGRAM_ID,
+                loaded_accounts_data_size_limit(0)
+            )]),
+            Err(TransactionError::InvalidLoadedAccountsDataSizeLimit)
+        );
+        assert_eq!(
+            process(&[(
+                COMPUTE_BUDGET_PROGRAM_ID,
+                loaded_accounts_data_size_limit(u32::MAX)
+            )])
+            .unwrap()
+            .loaded_accounts_bytes,
+            MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES
+        );
+    }
+
+    #[test]
+    fn test_default_compute_unit_limit() {
+        assert_eq!(process(&[]).unwrap().compute_unit_limit, 0);
+        assert_eq!(
+            process(&[
+                (OTHER_PROGRAM_ID, vec![]),
+                (COMPUTE_BUDGET_PROGRAM_ID, compute_unit_price(1)),
+                (OTHER_PROGRAM_ID, vec![]),
+            ])
+            .unwrap()
+            .compute_unit_limit,
+            2 * DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
+        );
+        assert_eq!(
+            process(&[const { (OTHER_PROGRAM_ID, Vec::new()) }; 8])
+                .unwrap()
+                .compute_unit_limit,
+            MAX_COMPUTE_UNIT_LIMIT
+        );
+        assert_eq!(
+            process(&[(COMPUTE_BUDGET_PROGRAM_ID, compute_unit_limit(u32::MAX))])
+                .unwrap()
+                .compute_unit_limit,
+            MAX_COMPUTE_UNIT_LIMIT
+        );
+    }
+
+    #[test]
+    fn test_prioritization_fee() {
+        let limits = |compute_unit_price, compute_unit_limit| ComputeBudgetLimits {
+            compute_unit_price,
+            compute_unit_limit,
+            ..ComputeBudgetLimits::default()
+        };
+        assert_eq!(limits(0, 200_000).prioritization_fee(), 0);
+        assert_eq!(limits(1, 1).prioritization_fee(), 1);
+        assert_eq!(limits(1, 1_000_000).prioritization_fee(), 1);
+        assert_eq!(limits(1, 1_000_001).prioritization_fee(), 2);
+        assert_eq!(limits(3, 500_000).prioritization_fee(), 2);
+        assert_eq!(
+            limits(u64::MAX, MAX_COMPUTE_UNIT_LIMIT).prioritization_fee(),
+            u64::MAX
+        );
+    }
+}
@@ -1,46 +1,218 @@
-use std::{collections::{HashMap, HashSet}, hash::RandomState, sync::Arc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::{btree_map, btree_set, BTreeMap, BTreeSet},
+    ops::Deref,
+    sync::Arc,
+};
 
+use crate::{
+    sysvar::{Clock, EpochRewards, EpochSchedule, LastRestartSlot, Rent, SysvarId},
+    transaction_context::Pubkey,
+    InstructionError,
+};
 
-pub type Pubkey = [u8; 32];
 /// Size of a hash in bytes.
 pub const HASH_BYTES: usize = 32;
-#[derive(Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(
+    Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize,
+)]
 pub struct Hash(pub(crate) [u8; HASH_BYTES]);
 
 pub type SlotHash = (u64, Hash);
 
-pub type StakeHistoryInner = StakeHistory;
-#[derive(PartialEq, Eq, Debug, Default)]
+pub type StakeHistoryInner = Vec<(u64, StakeHistoryEntry)>;
+#[derive(PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
 pub struct SlotHashes(Vec<SlotHash>);
 
-
-
-#[derive(Default, Clone, PartialEq, Eq, Debug)]
+#[derive(Default, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct StakeHistory(Arc<StakeHistoryInner>);
 
-#[derive(Debug, Clone)]
+/// Stake of the cluster at the end of an epoch
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct StakeHistoryEntry {
+    /// effective stake at this epoch
+    pub effective: u64,
+    /// sum of portion of stakes not fully warmed up
+    pub activating: u64,
+    /// requested to be cooled down, not fully deactivated yet
+    pub deactivating: u64,
+}
+
+/// Active and inactive runtime features
+///
+/// Both sides are kept in ordered collections rather than randomly seeded hash maps, so that
+/// lookups and iteration order are identical on every host and in the guest.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct FeatureSet {
-    pub active: AHashMap<Pubkey, u64>, // randomness
-    pub inactive: AHashSet<Pubkey>,  // randomness 
+    pub active: OrderedMap<Pubkey, u64>,
+    pub inactive: OrderedSet<Pubkey>,
+}
+
+impl FeatureSet {
+    pub fn new(active: OrderedMap<Pubkey, u64>, inactive: OrderedSet<Pubkey>) -> Self {
+        Self { active, inactive }
+    }
+
+    pub fn is_active(&self, feature_id: &Pubkey) -> bool {
+        self.active.contains_key(feature_id)
+    }
+
+    pub fn activated_slot(&self, feature_id: &Pubkey) -> Option<u64> {
+        self.active.get(feature_id).copied()
+    }
+
+    /// Activate a feature
+    pub fn activate(&mut self, feature_id: &Pubkey, slot: u64) {
+        self.inactive.remove(feature_id);
+        self.active.insert(*feature_id, slot);
+    }
+
+    /// Deactivate a feature
+    pub fn deactivate(&mut self, feature_id: &Pubkey) {
+        self.active.remove(feature_id);
+        self.inactive.insert(*feature_id);
+    }
+
+    /// Active features and their activation slot, in ascending key order
+    pub fn iter_active(&self) -> btree_map::Iter<'_, Pubkey, u64> {
+        self.active.iter()
+    }
+
+    /// Inactive features, in ascending key order
+    pub fn iter_inactive(&self) -> btree_set::Iter<'_, Pubkey> {
+        self.inactive.iter()
+    }
 }
 
-/// A [`HashMap`](std::collections::HashMap) using [`RandomState`](crate::RandomState) to hash the items.
-/// (Requires the `std` feature to be enabled.)
-#[derive(Clone, Debug)]
-pub struct AHashMap<K, V, S = RandomState>(HashMap<K, V, S>);
+/// 9bn2vTJUsUcnpiZWbu2woSKtTGW3ErZC9ERv88SDqQjK
+pub const ENABLE_PARTITIONED_EPOCH_REWARD: Pubkey = [
+    127, 198, 41, 123, 169, 152, 248, 91, 162, 72, 87, 192, 222, 6, 116, 99, 47, 86, 217, 90, 92,
+    197, 84, 49, 57, 65, 176, 19, 226, 83, 246, 242,
+];
+/// PERzQrt5gBD1XEe2c9XdFWqwgHY3mr7cYWbm5V772V8
+pub const PARTITIONED_EPOCH_REWARDS_SUPERFEATURE: Pubkey = [
+    5, 177, 246, 208, 160, 191, 252, 129, 188, 149, 222, 235, 53, 122, 136, 62, 166, 94, 68, 206,
+    181, 21, 90, 210, 176, 181, 87, 191, 135, 249, 75, 83,
+];
+/// HooKD5NC9QNxk25QuzCssB8ecrEzGt6eXEPBUxWp1LaR
+pub const LAST_RESTART_SLOT_SYSVAR: Pubkey = [
+    249, 184, 19, 75, 142, 102, 27, 64, 40, 28, 237, 190, 124, 120, 140, 153, 27, 147, 150, 52,
+    214, 201, 11, 222, 174, 184, 219, 90, 6, 125, 135, 142,
+];
+/// CLCoTADvV64PSrnR6QXty6Fwrt9Xc6EdxSJE4wLRePjq
+pub const GET_SYSVAR_SYSCALL_ENABLED: Pubkey = [
+    168, 91, 239, 82, 242, 98, 144, 130, 59, 210, 6, 221, 16, 231, 44, 91, 156, 34, 14, 150, 138,
+    167, 46, 152, 140, 123, 2, 57, 14, 213, 154, 244,
+];
+/// HTW2pSyErTj4BV6KBM9NZ9VBUJVxt7sacNWcf76wtzb3
+pub const BLAKE3_SYSCALL_ENABLED: Pubkey = [
+    244, 132, 234, 238, 184, 164, 148, 233, 40, 99, 70, 172, 70, 82, 255, 151, 162, 202, 13, 117,
+    41, 206, 89, 129, 20, 110, 131, 7, 31, 20, 157, 178,
+];
+
+/// A [`BTreeMap`] wrapper whose lookups and iteration are always in key order
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OrderedMap<K, V>(BTreeMap<K, V>);
+
+impl<K: Ord, V> OrderedMap<K, V> {
+    /// Inserts a key-value pair, returning the previous value of `key` if any
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.0.insert(key, value)
+    }
+
+    /// Removes `key`, returning its value if it was present
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.0.remove(key)
+    }
+}
+
+impl<K, V> Deref for OrderedMap<K, V> {
+    type Target = BTreeMap<K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for OrderedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self(BTreeMap::from_iter(iter))
+    }
+}
 
-/// A [`HashSet`](std::collections::HashSet) using [`RandomState`](crate::RandomState) to hash the items.
-/// (Requires the `std` feature to be enabled.)
-#[derive(Clone, Debug)]
-pub struct AHashSet<T, S = RandomState>(HashSet<T, S>);
+impl<K, V> IntoIterator for OrderedMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = btree_map::IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a OrderedMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = btree_map::Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// A [`BTreeSet`] wrapper whose lookups and iteration are always in key order
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OrderedSet<T>(BTreeSet<T>);
+
+impl<T: Ord> OrderedSet<T> {
+    /// Adds a value, returning whether it was newly inserted
+    pub fn insert(&mut self, value: T) -> bool {
+        self.0.insert(value)
+    }
+
+    /// Removes a value, returning whether it was present
+    pub fn remove(&mut self, value: &T) -> bool {
+        self.0.remove(value)
+    }
+}
+
+impl<T> Deref for OrderedSet<T> {
+    type Target = BTreeSet<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Ord> FromIterator<T> for OrderedSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(BTreeSet::from_iter(iter))
+    }
+}
+
+impl<T> IntoIterator for OrderedSet<T> {
+    type Item = T;
+    type IntoIter = btree_set::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a OrderedSet<T> {
+    type Item = &'a T;
+    type IntoIter = btree_set::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
 
-#[derive(Debug,Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Fees {
     pub fee_calculator: FeeCalculator,
 }
 
-#[derive(Default, PartialEq, Eq, Clone, Copy, Debug)]
-#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[derive(Default, PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FeeCalculator {
     /// The current cost of a signature.
     ///
@@ -49,10 +221,10 @@ pub struct FeeCalculator {
     pub lamports_per_signature: u64,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RecentBlockhashes(Vec<Entry>);
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Entry {
     pub blockhash: Hash,
     pub fee_calculator: FeeCalculator,
@@ -82,6 +254,219 @@ pub struct SysvarCache {
     recent_blockhashes: Option<RecentBlockhashes>,
 }
 
+impl SysvarCache {
+    /// Overwrite a sysvar. For testing purposes only.
+    pub fn set_sysvar_for_tests<T: SysvarId + Serialize>(&mut self, sysvar: &T) {
+        let data = bincode::serialize(sysvar).expect("Failed to serialize sysvar.");
+        match T::id() {
+            Clock::ID => {
+                self.clock = Some(data);
+            }
+            EpochRewards::ID => {
+                self.epoch_rewards = Some(data);
+            }
+            EpochSchedule::ID => {
+                self.epoch_schedule = Some(data);
+            }
+            Fees::ID => {
+                let fees: Fees =
+                    bincode::deserialize(&data).expect("Failed to deserialize Fees sysvar.");
+                self.fees = Some(fees);
+            }
+            LastRestartSlot::ID => {
+                self.last_restart_slot = Some(data);
+            }
+            RecentBlockhashes::ID => {
+                let recent_blockhashes: RecentBlockhashes = bincode::deserialize(&data)
+                    .expect("Failed to deserialize RecentBlockhashes sysvar.");
+                self.recent_blockhashes = Some(recent_blockhashes);
+            }
+            Rent::ID => {
+                self.rent = Some(data);
+            }
+            SlotHashes::ID => {
+                let slot_hashes: SlotHashes =
+                    bincode::deserialize(&data).expect("Failed to deserialize SlotHashes sysvar.");
+                self.slot_hashes = Some(data);
+                self.slot_hashes_obj = Some(Arc::new(slot_hashes));
+            }
+            StakeHistory::ID => {
+                let stake_history: StakeHistory = bincode::deserialize(&data)
+                    .expect("Failed to deserialize StakeHistory sysvar.");
+                self.stake_history = Some(data);
+                self.stake_history_obj = Some(Arc::new(stake_history));
+            }
+            sysvar_id => panic!("Unrecognized Sysvar ID: {sysvar_id:?}"),
+        }
+    }
+
+    /// Raw account data of a sysvar, exposed for `sol_get_sysvar`
+    pub fn sysvar_id_to_buffer(&self, sysvar_id: &Pubkey) -> &Option<Vec<u8>> {
+        if Clock::check_id(sysvar_id) {
+            &self.clock
+        } else if EpochSchedule::check_id(sysvar_id) {
+            &self.epoch_schedule
+        } else if EpochRewards::check_id(sysvar_id) {
+            &self.epoch_rewards
+        } else if Rent::check_id(sysvar_id) {
+            &self.rent
+        } else if SlotHashes::check_id(sysvar_id) {
+            &self.slot_hashes
+        } else if StakeHistory::check_id(sysvar_id) {
+            &self.stake_history
+        } else if LastRestartSlot::check_id(sysvar_id) {
+            &self.last_restart_slot
+        } else {
+            &None
+        }
+    }
+
+    fn get_sysvar_obj<T: DeserializeOwned>(
+        &self,
+        sysvar_id: &Pubkey,
+    ) -> Result<Arc<T>, InstructionError> {
+        if let Some(ref sysvar_buf) = self.sysvar_id_to_buffer(sysvar_id) {
+            bincode::deserialize(sysvar_buf)
+                .map(Arc::new)
+                .map_err(|_| InstructionError::UnsupportedSysvar)
+        } else {
+            Err(InstructionError::UnsupportedSysvar)
+        }
+    }
+
+    pub fn get_clock(&self) -> Result<Arc<Clock>, InstructionError> {
+        self.get_sysvar_obj(&Clock::id())
+    }
+
+    pub fn get_epoch_schedule(&self) -> Result<Arc<EpochSchedule>, InstructionError> {
+        self.get_sysvar_obj(&EpochSchedule::id())
+    }
+
+    pub fn get_epoch_rewards(&self) -> Result<Arc<EpochRewards>, InstructionError> {
+        self.get_sysvar_obj(&EpochRewards::id())
+    }
+
+    pub fn get_rent(&self) -> Result<Arc<Rent>, InstructionError> {
+        self.get_sysvar_obj(&Rent::id())
+    }
+
+    pub fn get_last_restart_slot(&self) -> Result<Arc<LastRestartSlot>, InstructionError> {
+        self.get_sysvar_obj(&LastRestartSlot::id())
+    }
+
+    pub fn get_stake_history(&self) -> Result<Arc<StakeHistory>, InstructionError> {
+        self.stake_history_obj
+            .clone()
+            .ok_or(InstructionError::UnsupportedSysvar)
+    }
+
+    pub fn get_slot_hashes(&self) -> Result<Arc<SlotHashes>, InstructionError> {
+        self.slot_hashes_obj
+            .clone()
+            .ok_or(InstructionError::UnsupportedSysvar)
+    }
+
+    pub fn get_fees(&self) -> Result<Arc<Fees>, InstructionError> {
+        self.fees
+            .clone()
+            .ok_or(InstructionError::UnsupportedSysvar)
+            .map(Arc::new)
+    }
+
+    pub fn get_recent_blockhashes(&self) -> Result<Arc<RecentBlockhashes>, InstructionError> {
+        self.recent_blockhashes
+            .clone()
+            .ok_or(InstructionError::UnsupportedSysvar)
+            .map(Arc::new)
+    }
+
+    /// Loads every sysvar not set yet through `get_account_data`, which is called with
+    /// the sysvar id and a callback taking the account data. Data that does not decode
+    /// is ignored.
+    pub fn fill_missing_entries<F: FnMut(&Pubkey, &mut dyn FnMut(&[u8]))>(
+        &mut self,
+        mut get_account_data: F,
+    ) {
+        if self.clock.is_none() {
+            get_account_data(&Clock::id(), &mut |data: &[u8]| {
+                if bincode::deserialize::<Clock>(data).is_ok() {
+                    self.clock = Some(data.to_vec());
+                }
+            });
+        }
+
+        if self.epoch_schedule.is_none() {
+            get_account_data(&EpochSchedule::id(), &mut |data: &[u8]| {
+                if bincode::deserialize::<EpochSchedule>(data).is_ok() {
+                    self.epoch_schedule = Some(data.to_vec());
+                }
+            });
+        }
+
+        if self.epoch_rewards.is_none() {
+            get_account_data(&EpochRewards::id(), &mut |data: &[u8]| {
+                if bincode::deserialize::<EpochRewards>(data).is_ok() {
+                    self.epoch_rewards = Some(data.to_vec());
+                }
+            });
+        }
+
+        if self.rent.is_none() {
+            get_account_data(&Rent::id(), &mut |data: &[u8]| {
+                if bincode::deserialize::<Rent>(data).is_ok() {
+                    self.rent = Some(data.to_vec());
+                }
+            });
+        }
+
+        if self.slot_hashes.is_none() {
+            get_account_data(&SlotHashes::id(), &mut |data: &[u8]| {
+                if let Ok(obj) = bincode::deserialize::<SlotHashes>(data) {
+                    self.slot_hashes = Some(data.to_vec());
+                    self.slot_hashes_obj = Some(Arc::new(obj));
+                }
+            });
+        }
+
+        if self.stake_history.is_none() {
+            get_account_data(&StakeHistory::id(), &mut |data: &[u8]| {
+                if let Ok(obj) = bincode::deserialize::<StakeHistory>(data) {
+                    self.stake_history = Some(data.to_vec());
+                    self.stake_history_obj = Some(Arc::new(obj));
+                }
+            });
+        }
+
+        if self.last_restart_slot.is_none() {
+            get_account_data(&LastRestartSlot::id(), &mut |data: &[u8]| {
+                if bincode::deserialize::<LastRestartSlot>(data).is_ok() {
+                    self.last_restart_slot = Some(data.to_vec());
+                }
+            });
+        }
+
+        if self.fees.is_none() {
+            get_account_data(&Fees::id(), &mut |data: &[u8]| {
+                if let Ok(fees) = bincode::deserialize(data) {
+                    self.fees = Some(fees);
+                }
+            });
+        }
+
+        if self.recent_blockhashes.is_none() {
+            get_account_data(&RecentBlockhashes::id(), &mut |data: &[u8]| {
+                if let Ok(recent_blockhashes) = bincode::deserialize(data) {
+                    self.recent_blockhashes = Some(recent_blockhashes);
+                }
+            });
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
 pub struct EnvironmentConfig<'a> {
     pub blockhash: Hash,
     pub blockhash_lamports_per_signature: u64,
@@ -89,4 +474,169 @@ pub struct EnvironmentConfig<'a> {
     get_epoch_vote_account_stake_callback: &'a dyn Fn(&'a Pubkey) -> u64,
     pub feature_set: Arc<FeatureSet>,
     sysvar_cache: &'a SysvarCache,
-}
\ No newline at end of file
+}
+
+impl<'a> EnvironmentConfig<'a> {
+    pub fn new(
+        blockhash: Hash,
+        blockhash_lamports_per_signature: u64,
+        epoch_total_stake: u64,
+        get_epoch_vote_account_stake_callback: &'a dyn Fn(&'a Pubkey) -> u64,
+        feature_set: Arc<FeatureSet>,
+        sysvar_cache: &'a SysvarCache,
+    ) -> Self {
+        Self {
+            blockhash,
+            blockhash_lamports_per_signature,
+            epoch_total_stake,
+            get_epoch_vote_account_stake_callback,
+            feature_set,
+            sysvar_cache,
+        }
+    }
+
+    pub fn get_sysvar_cache(&self) -> &'a SysvarCache {
+        self.sysvar_cache
+    }
+
+    pub fn get_epoch_total_stake(&self) -> u64 {
+        self.epoch_total_stake
+    }
+
+    pub fn get_epoch_vote_account_stake(&self, pubkey: &'a Pubkey) -> u64 {
+        (self.get_epoch_vote_account_stake_callback)(pubkey)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_set_activate_deactivate() {
+        let mut feature_set = FeatureSet::new(
+            OrderedMap::default(),
+            [[1; 32], [2; 32]].into_iter().collect(),
+        );
+
+        feature_set.activate(&[2; 32], 10);
+        assert!(feature_set.is_active(&[2; 32]));
+        assert_eq!(feature_set.activated_slot(&[2; 32]), Some(10));
+        assert!(!feature_set.inactive.contains(&[2; 32]));
+        assert!(!feature_set.is_active(&[1; 32]));
+        assert!(feature_set.inactive.contains(&[1; 32]));
+
+        feature_set.deactivate(&[2; 32]);
+        assert!(!feature_set.is_active(&[2; 32]));
+        assert_eq!(feature_set.activated_slot(&[2; 32]), None);
+        assert!(feature_set.inactive.contains(&[2; 32]));
+        assert_eq!(feature_set.inactive.len(), 2);
+    }
+
+    #[test]
+    fn test_feature_set_iteration_order() {
+        let mut feature_set = FeatureSet::default();
+        for (id, slot) in [(7u8, 1), (3, 2), (200, 3), (0, 4), (42, 5)] {
+            feature_set.activate(&[id; 32], slot);
+        }
+        for id in [9u8, 1, 255] {
+            feature_set.deactivate(&[id; 32]);
+        }
+
+        let active: Vec<_> = feature_set
+            .iter_active()
+            .map(|(id, slot)| (id[0], *slot))
+            .collect();
+        assert_eq!(active, vec![(0, 4), (3, 2), (7, 1), (42, 5), (200, 3)]);
+        let inactive: Vec<_> = feature_set.iter_inactive().map(|id| id[0]).collect();
+        assert_eq!(inactive, vec![1, 9, 255]);
+    }
+
+    #[test]
+    fn test_sysvar_cache_fill_missing_entries() {
+        let cached_clock = Clock {
+            slot: 1,
+            ..Clock::default()
+        };
+        let account_clock = Clock {
+            slot: 2,
+            ..Clock::default()
+        };
+        let rent = Rent {
+            lamports_per_byte_year: 7,
+            ..Rent::default()
+        };
+        let mut sysvar_cache = SysvarCache::default();
+        sysvar_cache.set_sysvar_for_tests(&cached_clock);
+
+        let mut requested = Vec::new();
+        sysvar_cache.fill_missing_entries(|sysvar_id, set_sysvar| {
+            requested.push(*sysvar_id);
+            if *sysvar_id == Clock::id() {
+                set_sysvar(&bincode::serialize(&account_clock).unwrap());
+            } else if *sysvar_id == Rent::id() {
+                set_sysvar(&bincode::serialize(&rent).unwrap());
+            } else if *sysvar_id == EpochSchedule::id() {
+                // Does not decode, so the entry stays missing
+                set_sysvar(&[1, 2]);
+            }
+        });
+
+        assert!(!requested.contains(&Clock::id()));
+        assert!(requested.contains(&Rent::id()));
+        assert_eq!(*sysvar_cache.get_clock().unwrap(), cached_clock);
+        assert_eq!(*sysvar_cache.get_rent().unwrap(), rent);
+        assert_eq!(
+            sysvar_cache.get_epoch_schedule(),
+            Err(InstructionError::UnsupportedSysvar)
+        );
+
+        // Entries loaded once are not requested again
+        let mut requested = Vec::new();
+        sysvar_cache.fill_missing_entries(|sysvar_id, _| requested.push(*sysvar_id));
+        assert!(!requested.contains(&Clock::id()));
+        assert!(!requested.contains(&Rent::id()));
+        assert!(requested.contains(&EpochSchedule::id()));
+    }
+
+    #[test]
+    fn test_sysvar_cache_unsupported_sysvar() {
+        let sysvar_cache = SysvarCache::default();
+        assert_eq!(
+            sysvar_cache.get_clock(),
+            Err(InstructionError::UnsupportedSysvar)
+        );
+        assert_eq!(
+            sysvar_cache.get_epoch_schedule(),
+            Err(InstructionError::UnsupportedSysvar)
+        );
+        assert_eq!(
+            sysvar_cache.get_epoch_rewards(),
+            Err(InstructionError::UnsupportedSysvar)
+        );
+        assert_eq!(
+            sysvar_cache.get_rent(),
+            Err(InstructionError::UnsupportedSysvar)
+        );
+        assert_eq!(
+            sysvar_cache.get_last_restart_slot(),
+            Err(InstructionError::UnsupportedSysvar)
+        );
+        assert_eq!(
+            sysvar_cache.get_stake_history(),
+            Err(InstructionError::UnsupportedSysvar)
+        );
+        assert_eq!(
+            sysvar_cache.get_slot_hashes(),
+            Err(InstructionError::UnsupportedSysvar)
+        );
+        assert_eq!(
+            sysvar_cache.get_fees(),
+            Err(InstructionError::UnsupportedSysvar)
+        );
+        assert_eq!(
+            sysvar_cache.get_recent_blockhashes(),
+            Err(InstructionError::UnsupportedSysvar)
+        );
+    }
+}
@@ -1,22 +1,46 @@
-mod transaction_context;
-mod program_cache_for_tx_batch;
-mod log_collector;
+#![deny(clippy::disallowed_types)]
+
 mod compute_budget;
+mod compute_budget_processor;
+mod environment_config;
+mod instruction_error;
+mod log_collector;
 mod measure;
-mod timings;
+mod program_cache_for_tx_batch;
+mod rent_state;
+mod serialization;
+pub mod stable_log;
 mod syscall_context;
-mod environment_config;
+mod syscalls;
+pub mod sysvar;
+mod timings;
+mod transaction_context;
+mod transaction_error;
 
-pub use transaction_context::*;
-pub use program_cache_for_tx_batch::*;
-pub use log_collector::*;
 pub use compute_budget::*;
+pub use compute_budget_processor::*;
+pub use environment_config::*;
+pub use instruction_error::*;
+pub use log_collector::*;
 pub use measure::*;
-pub use timings::*;
+pub use program_cache_for_tx_batch::*;
+pub use rent_state::*;
+pub use serialization::*;
 pub use syscall_context::*;
-pub use environment_config::*;
+pub use syscalls::*;
+pub use timings::*;
+pub use transaction_context::*;
+pub use transaction_error::*;
 
-use std::{cell::RefCell, rc::Rc};
+use solana_sbpf::{
+    aligned_memory::AlignedMemory,
+    ebpf::{self, HOST_ALIGN},
+    elf::Executable,
+    error::{EbpfError, ProgramResult},
+    memory_region::{MemoryMapping, MemoryRegion},
+    vm::{ContextObject, EbpfVm},
+};
+use std::{cell::RefCell, mem, rc::Rc};
 pub struct InvokeContext<'a> {
     /// Information about the currently executing transaction.
     pub transaction_context: &'a mut TransactionContext,
@@ -32,7 +56,620 @@ pub struct InvokeContext<'a> {
     log_collector: Option<Rc<RefCell<LogCollector>>>,
     /// Latest measurement not yet accumulated in [ExecuteDetailsTimings::execute_us]
     pub execute_time: Option<Measure>,
+    /// Clock backing [Self::execute_time] and the [ExecuteDetailsTimings] measurements
+    pub clock: Rc<dyn Clock>,
     pub timings: ExecuteDetailsTimings,
     pub syscall_context: Vec<Option<SyscallContext>>,
     traces: Vec<Vec<[u64; 12]>>,
 }
+
+impl<'a> InvokeContext<'a> {
+    pub fn new(
+        transaction_context: &'a mut TransactionContext,
+        program_cache_for_tx_batch: &'a mut ProgramCacheForTxBatch,
+        environment_config: EnvironmentConfig<'a>,
+        log_collector: Option<Rc<RefCell<LogCollector>>>,
+        compute_budget: ComputeBudget,
+    ) -> Self {
+        Self {
+            transaction_context,
+            program_cache_for_tx_batch,
+            environment_config,
+            log_collector,
+            compute_budget,
+            compute_meter: RefCell::new(compute_budget.compute_unit_limit),
+            execute_time: None,
+            clock: default_clock(),
+            timings: ExecuteDetailsTimings::default(),
+            syscall_context: Vec::new(),
+            traces: Vec::new(),
+        }
+    }
+
+    /// Push a stack frame onto the invocation stack
+    pub fn push(&mut self) -> Result<(), InstructionError> {
+        let instruction_context = self
+            .transaction_context
+            .get_instruction_context_at_index_in_trace(
+                self.transaction_context.get_instruction_trace_length(),
+            )?;
+        let program_id = instruction_context
+            .get_last_program_key(self.transaction_context)
+            .map_err(|_| InstructionError::UnsupportedProgramId)?;
+        if self
+            .transaction_context
+            .get_instruction_context_stack_height()
+            != 0
+        {
+            let contains = (0..self
+                .transaction_context
+                .get_instruction_context_stack_height())
+                .any(|level| {
+                    self.transaction_context
+                        .get_instruction_context_at_nesting_level(level)
+                        .and_then(|instruction_context| {
+                            instruction_context.get_last_program_key(self.transaction_context)
+                        })
+                        .map(|key| key == program_id)
+                        .unwrap_or(false)
+                });
+            let is_last = self
+                .transaction_context
+                .get_current_instruction_context()
+                .and_then(|instruction_context| {
+                    instruction_context.get_last_program_key(self.transaction_context)
+                })
+                .map(|key| key == program_id)
+                .unwrap_or(false);
+            if contains && !is_last {
+                // Reentrancy not allowed unless caller is calling itself
+                return Err(InstructionError::ReentrancyNotAllowed);
+            }
+        }
+
+        self.syscall_context.push(None);
+        self.transaction_context.push()
+    }
+
+    /// Pop a stack frame from the invocation stack
+    pub fn pop(&mut self) -> Result<(), InstructionError> {
+        if let Some(Some(syscall_context)) = self.syscall_context.pop() {
+            self.traces.push(syscall_context.trace_log);
+        }
+        self.transaction_context.pop()
+    }
+
+    /// Current height of the invocation stack, top level instructions are height 1
+    pub fn get_stack_height(&self) -> usize {
+        self.transaction_context
+            .get_instruction_context_stack_height()
+    }
+
+    /// Processes an instruction and returns how many compute units were used
+    pub fn process_instruction(
+        &mut self,
+        instruction_data: &[u8],
+        instruction_accounts: &[InstructionAccount],
+        program_indices: &[IndexOfAccount],
+        compute_units_consumed: &mut u64,
+    ) -> Result<(), InstructionError> {
+        *compute_units_consumed = 0;
+        self.transaction_context
+            .get_next_instruction_context()?
+            .configure(program_indices, instruction_accounts, instruction_data);
+        self.push()?;
+        self.process_executable_chain(compute_units_consumed)
+            // MUST pop if and only if `push` succeeded, independent of `result`.
+            // Thus, the `.and()` instead of an `.and_then()`.
+            .and(self.pop())
+    }
+
+    /// Looks up the program of the current instruction in the cache and runs it
+    fn process_executable_chain(
+        &mut self,
+        compute_units_consumed: &mut u64,
+    ) -> Result<(), InstructionError> {
+        let instruction_context = self.transaction_context.get_current_instruction_context()?;
+        let program_id = *instruction_context
+            .get_last_program_key(self.transaction_context)
+            .map_err(|_| InstructionError::UnsupportedProgramId)?;
+        let entry = self
+            .program_cache_for_tx_batch
+            .find(&program_id)
+            .ok_or(InstructionError::UnsupportedProgramId)?;
+        self.transaction_context
+            .set_return_data(program_id, Vec::new())?;
+        let logger = self.get_log_collector();
+        stable_log::program_invoke(&logger, &program_id, self.get_stack_height());
+        let clock = Rc::clone(&self.clock);
+        let process_executable_chain_time = Measure::start("process_executable_chain", &clock);
+        let pre_remaining_units = self.get_remaining();
+        let result = match &entry.program {
+            ProgramCacheEntryType::Loaded(executable) => self.execute(executable),
+            _ => Err(InstructionError::UnsupportedProgramId.into()),
+        };
+        let result = match result {
+            Ok(()) => {
+                stable_log::program_success(&logger, &program_id);
+                Ok(())
+            }
+            Err(err) => {
+                if let Some(instruction_err) = err.downcast_ref::<InstructionError>() {
+                    stable_log::program_failure(&logger, &program_id, instruction_err);
+                    Err(instruction_err.clone())
+                } else {
+                    stable_log::program_failure(&logger, &program_id, &err);
+                    Err(InstructionError::ProgramFailedToComplete)
+                }
+            }
+        };
+        let post_remaining_units = self.get_remaining();
+        *compute_units_consumed = pre_remaining_units.saturating_sub(post_remaining_units);
+        self.timings.accumulate_program(
+            &program_id,
+            process_executable_chain_time.end_as_ticks(),
+            *compute_units_consumed,
+            result.is_err(),
+        );
+        result
+    }
+
+    /// Serializes the instruction, runs the SBF program in the interpreter and writes
+    /// the accounts back
+    ///
+    /// Errors are returned unconverted so the failure log line shows the original cause.
+    fn execute(
+        &mut self,
+        executable: &Executable<InvokeContext<'static>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // We dropped the lifetime tracking in the program cache by setting it to 'static,
+        // thus we need to reintroduce the correct lifetime of InvokeContext here again.
+        let executable = unsafe {
+            mem::transmute::<&Executable<InvokeContext<'static>>, &Executable<InvokeContext<'a>>>(
+                executable,
+            )
+        };
+        let clock = Rc::clone(&self.clock);
+        let compute_budget = self.compute_budget;
+
+        let serialize_time = Measure::start("serialize", &clock);
+        let (mut parameter_bytes, accounts_metadata) = serialization::serialize_parameters(
+            self.transaction_context,
+            self.transaction_context.get_current_instruction_context()?,
+        )?;
+        self.timings.serialize_us += serialize_time.end_as_ticks();
+
+        let log_collector = self.get_log_collector();
+        let program_id = *self
+            .transaction_context
+            .get_current_instruction_context()?
+            .get_last_program_key(self.transaction_context)?;
+
+        let create_vm_time = Measure::start("create_vm", &clock);
+        let compute_meter_prev = self.get_remaining();
+        self.consume_checked(
+            compute_budget
+                .heap_cost(compute_budget.heap_size)
+                .unwrap_or(u64::MAX),
+        )
+        .map_err(|_| InstructionError::ProgramEnvironmentSetupFailure)?;
+        let stack_size = compute_budget
+            .max_call_depth
+            .saturating_mul(compute_budget.stack_frame_size);
+        let mut stack = AlignedMemory::<HOST_ALIGN>::zero_filled(stack_size);
+        let mut heap = AlignedMemory::<HOST_ALIGN>::zero_filled(compute_budget.heap_size as usize);
+        self.set_syscall_context(SyscallContext {
+            allocator: BpfAllocator::new(heap.len() as u64),
+            accounts_metadata,
+            trace_log: Vec::new(),
+        })?;
+        let config = executable.get_config();
+        let sbpf_version = executable.get_sbpf_version();
+        let regions = vec![
+            executable.get_ro_region(),
+            MemoryRegion::new_writable_gapped(
+                stack.as_slice_mut(),
+                ebpf::MM_STACK_START,
+                if !sbpf_version.dynamic_stack_frames() && config.enable_stack_frame_gaps {
+                    config.stack_frame_size as u64
+                } else {
+                    0
+                },
+            ),
+            MemoryRegion::new_writable(heap.as_slice_mut(), ebpf::MM_HEAP_START),
+            MemoryRegion::new_writable(parameter_bytes.as_slice_mut(), ebpf::MM_INPUT_START),
+        ];
+        let memory_mapping = MemoryMapping::new(regions, config, sbpf_version)
+            .map_err(|_| InstructionError::ProgramEnvironmentSetupFailure)?;
+        let mut vm = EbpfVm::new(
+            executable.get_loader().clone(),
+            sbpf_version,
+            self,
+            memory_mapping,
+            stack_size,
+        );
+        let create_vm_ticks = create_vm_time.end_as_ticks();
+
+        let execute_time = Measure::start("execute", &clock);
+        let (compute_units_consumed, result) = vm.execute_program(executable, true);
+        drop(vm);
+        self.timings.create_vm_us += create_vm_ticks;
+        self.timings.accumulate_execute(execute_time);
+        stable_log::program_consumed(
+            &log_collector,
+            &program_id,
+            compute_units_consumed,
+            compute_meter_prev,
+        );
+        let (_returned_from_program_id, return_data) = self.transaction_context.get_return_data();
+        if !return_data.is_empty() {
+            stable_log::program_return(&log_collector, &program_id, return_data);
+        }
+        let result = match result {
+            ProgramResult::Ok(status) if status != SUCCESS => {
+                Err(Box::new(InstructionError::from(status)) as Box<dyn std::error::Error>)
+            }
+            ProgramResult::Ok(_) => Ok(()),
+            ProgramResult::Err(EbpfError::SyscallError(err)) => Err(err),
+            ProgramResult::Err(error) => Err(error.into()),
+        };
+
+        let deserialize_time = Measure::start("deserialize", &clock);
+        let result = result.and_then(|_| {
+            serialization::deserialize_parameters(
+                self.transaction_context,
+                self.transaction_context.get_current_instruction_context()?,
+                parameter_bytes.as_slice(),
+                &self.get_syscall_context()?.accounts_metadata,
+            )
+            .map_err(|err| err.into())
+        });
+        self.timings.deserialize_us += deserialize_time.end_as_ticks();
+        result
+    }
+
+    /// Set this instruction's SyscallContext
+    pub fn set_syscall_context(
+        &mut self,
+        syscall_context: SyscallContext,
+    ) -> Result<(), InstructionError> {
+        *self
+            .syscall_context
+            .last_mut()
+            .ok_or(InstructionError::CallDepth)? = Some(syscall_context);
+        Ok(())
+    }
+
+    /// Get this instruction's SyscallContext
+    pub fn get_syscall_context(&self) -> Result<&SyscallContext, InstructionError> {
+        self.syscall_context
+            .last()
+            .and_then(std::option::Option::as_ref)
+            .ok_or(InstructionError::CallDepth)
+    }
+
+    /// Get this instruction's SyscallContext
+    pub fn get_syscall_context_mut(&mut self) -> Result<&mut SyscallContext, InstructionError> {
+        self.syscall_context
+            .last_mut()
+            .and_then(|syscall_context| syscall_context.as_mut())
+            .ok_or(InstructionError::CallDepth)
+    }
+
+    /// Whether pointers passed to syscalls must be aligned
+    ///
+    /// Only the aligned input layout is supported, so this always holds.
+    pub fn get_check_aligned(&self) -> bool {
+        true
+    }
+
+    /// Get this invocation's LogCollector
+    pub fn get_log_collector(&self) -> Option<Rc<RefCell<LogCollector>>> {
+        self.log_collector.clone()
+    }
+
+    /// Consume compute units
+    ///
+    /// Exceeding the limit drains the meter to zero before failing, like Agave.
+    pub fn consume_checked(&self, amount: u64) -> Result<(), Box<dyn std::error::Error>> {
+        let mut compute_meter = self.compute_meter.borrow_mut();
+        let exceeded = *compute_meter < amount;
+        self.clock.advance(amount.min(*compute_meter));
+        *compute_meter = compute_meter.saturating_sub(amount);
+        if exceeded {
+            return Err(Box::new(InstructionError::ComputationalBudgetExceeded));
+        }
+        Ok(())
+    }
+
+    /// Set compute units
+    ///
+    /// Only use for tests and benchmarks
+    pub fn mock_set_remaining(&self, remaining: u64) {
+        *self.compute_meter.borrow_mut() = remaining;
+    }
+
+    /// Get the number of compute units left in the meter
+    pub fn get_remaining(&self) -> u64 {
+        *self.compute_meter.borrow()
+    }
+
+    /// Return a references to traces
+    pub fn get_traces(&self) -> &Vec<Vec<[u64; 12]>> {
+        &self.traces
+    }
+
+    /// Get this invocation's compute budget
+    pub fn get_compute_budget(&self) -> &ComputeBudget {
+        &self.compute_budget
+    }
+
+    /// Get the current feature set.
+    pub fn get_feature_set(&self) -> &FeatureSet {
+        &self.environment_config.feature_set
+    }
+
+    /// Get cached sysvars
+    pub fn get_sysvar_cache(&self) -> &SysvarCache {
+        self.environment_config.get_sysvar_cache()
+    }
+}
+
+impl ContextObject for InvokeContext<'_> {
+    /// Records the register state into the current frame's trace log, which is moved
+    /// into `traces` once the frame is popped.
+    fn trace(&mut self, state: [u64; 12]) {
+        if let Some(Some(syscall_context)) = self.syscall_context.last_mut() {
+            syscall_context.trace_log.push(state);
+        }
+    }
+
+    fn consume(&mut self, amount: u64) {
+        let mut compute_meter = self.compute_meter.borrow_mut();
+        self.clock.advance(amount.min(*compute_meter));
+        *compute_meter = compute_meter.saturating_sub(amount);
+    }
+
+    fn get_remaining(&self) -> u64 {
+        *self.compute_meter.borrow()
+    }
+}
+
+/// Declares `$invoke_context` over a [TransactionContext] named `$transaction_context`
+/// holding `$transaction_accounts`, with an empty program cache and sysvar cache.
+#[cfg(test)]
+macro_rules! with_mock_invoke_context {
+    ($invoke_context:ident, $transaction_context:ident, $transaction_accounts:expr) => {
+        let compute_budget = $crate::ComputeBudget::default();
+        let mut $transaction_context = $crate::TransactionContext::new(
+            $transaction_accounts,
+            $crate::sysvar::Rent::default(),
+            compute_budget.max_instruction_stack_depth,
+            compute_budget.max_instruction_trace_length,
+        );
+        let program_runtime_environment: $crate::ProgramRuntimeEnvironment =
+            std::sync::Arc::new(solana_sbpf::program::BuiltinProgram::new_mock());
+        let mut program_cache_for_tx_batch = $crate::ProgramCacheForTxBatch::new(
+            0,
+            $crate::ProgramRuntimeEnvironments {
+                program_runtime_v1: program_runtime_environment.clone(),
+                program_runtime_v2: program_runtime_environment,
+            },
+            None,
+            0,
+        );
+        let sysvar_cache = $crate::SysvarCache::default();
+        let get_epoch_vote_account_stake = |_: &$crate::Pubkey| 0;
+        let environment_config = $crate::EnvironmentConfig::new(
+            $crate::Hash::default(),
+            0,
+            0,
+            &get_epoch_vote_account_stake,
+            std::sync::Arc::default(),
+            &sysvar_cache,
+        );
+        #[allow(unused_mut)]
+        let mut $invoke_context = $crate::InvokeContext::new(
+            &mut $transaction_context,
+            &mut program_cache_for_tx_batch,
+            environment_config,
+            None,
+            compute_budget,
+        );
+    };
+}
+#[cfg(test)]
+pub(crate) use with_mock_invoke_context;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sbpf::{
+        assembler::assemble, declare_builtin_function, program::BuiltinProgram,
+        program::SBPFVersion, vm::Config,
+    };
+    use std::sync::Arc;
+
+    #[test]
+    fn test_consume_checked() {
+        with_mock_invoke_context!(invoke_context, transaction_context, vec![]);
+        let clock = Rc::new(CycleClock::default());
+        invoke_context.clock = clock.clone();
+
+        invoke_context.mock_set_remaining(100);
+        invoke_context.consume_checked(60).unwrap();
+        assert_eq!(invoke_context.get_remaining(), 40);
+        invoke_context.consume_checked(40).unwrap();
+        assert_eq!(invoke_context.get_remaining(), 0);
+        assert_eq!(clock.now(), 100);
+
+        invoke_context.mock_set_remaining(30);
+        let err = invoke_context.consume_checked(31).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<InstructionError>(),
+            Some(&InstructionError::ComputationalBudgetExceeded)
+        );
+        assert_eq!(invoke_context.get_remaining(), 0);
+        assert_eq!(clock.now(), 130);
+    }
+
+    #[test]
+    fn test_consume_advances_clock() {
+        with_mock_invoke_context!(invoke_context, transaction_context, vec![]);
+        let clock = Rc::new(CycleClock::default());
+        invoke_context.clock = clock.clone();
+
+        invoke_context.mock_set_remaining(50);
+        ContextObject::consume(&mut invoke_context, 20);
+        assert_eq!(ContextObject::get_remaining(&invoke_context), 30);
+        assert_eq!(clock.now(), 20);
+        ContextObject::consume(&mut invoke_context, 45);
+        assert_eq!(ContextObject::get_remaining(&invoke_context), 0);
+        assert_eq!(clock.now(), 50);
+    }
+
+    fn push_program(
+        invoke_context: &mut InvokeContext,
+        program_index: IndexOfAccount,
+    ) -> Result<(), InstructionError> {
+        invoke_context
+            .transaction_context
+            .get_next_instruction_context()?
+            .configure(&[program_index], &[], &[]);
+        invoke_context.push()
+    }
+
+    #[test]
+    fn test_push_reentrancy() {
+        let transaction_accounts = vec![
+            ([1; 32], AccountSharedData::default()),
+            ([2; 32], AccountSharedData::default()),
+        ];
+        with_mock_invoke_context!(invoke_context, transaction_context, transaction_accounts);
+
+        push_program(&mut invoke_context, 0).unwrap();
+        // A program may call itself
+        push_program(&mut invoke_context, 0).unwrap();
+        push_program(&mut invoke_context, 1).unwrap();
+        assert_eq!(
+            push_program(&mut invoke_context, 0),
+            Err(InstructionError::ReentrancyNotAllowed)
+        );
+        assert_eq!(invoke_context.get_stack_height(), 3);
+
+        invoke_context.pop().unwrap();
+        push_program(&mut invoke_context, 0).unwrap();
+        assert_eq!(invoke_context.get_stack_height(), 3);
+    }
+
+    #[test]
+    fn test_push_call_depth() {
+        with_mock_invoke_context!(
+            invoke_context,
+            transaction_context,
+            vec![([1; 32], AccountSharedData::default())]
+        );
+        let max_depth = invoke_context
+            .get_compute_budget()
+            .max_instruction_stack_depth;
+
+        for _ in 0..max_depth {
+            push_program(&mut invoke_context, 0).unwrap();
+        }
+        assert_eq!(
+            push_program(&mut invoke_context, 0),
+            Err(InstructionError::CallDepth)
+        );
+        assert_eq!(invoke_context.get_stack_height(), max_depth);
+        for _ in 0..max_depth {
+            invoke_context.pop().unwrap();
+        }
+        assert_eq!(invoke_context.pop(), Err(InstructionError::CallDepth));
+    }
+
+    const PROGRAM_ID: Pubkey = [1; 32];
+
+    declare_builtin_function!(
+        /// Fails with `InvalidAccountData` if its first argument is zero, or with an error
+        /// that is not an [InstructionError] otherwise
+        SyscallFail,
+        fn rust(
+            _invoke_context: &mut InvokeContext,
+            kind: u64,
+            _arg_b: u64,
+            _arg_c: u64,
+            _arg_d: u64,
+            _arg_e: u64,
+            _memory_mapping: &mut MemoryMapping,
+        ) -> Result<u64, Box<dyn std::error::Error>> {
+            if kind == 0 {
+                Err(Box::new(InstructionError::InvalidAccountData))
+            } else {
+                Err("not an instruction error".into())
+            }
+        }
+    );
+
+    /// Assembles `source`, runs it as the only instruction of a transaction and returns
+    /// the result with the compute units it consumed and its timing entry
+    fn process_program(source: &str) -> (Result<(), InstructionError>, u64, ProgramTiming) {
+        let mut loader = BuiltinProgram::new_loader(Config {
+            enabled_sbpf_versions: SBPFVersion::V0..=SBPFVersion::V0,
+            ..Config::default()
+        });
+        loader.register_function("fail", SyscallFail::vm).unwrap();
+        let executable = assemble::<InvokeContext<'static>>(source, Arc::new(loader)).unwrap();
+        with_mock_invoke_context!(
+            invoke_context,
+            transaction_context,
+            vec![(PROGRAM_ID, AccountSharedData::default())]
+        );
+        invoke_context.program_cache_for_tx_batch.replenish(
+            PROGRAM_ID,
+            Arc::new(ProgramCacheEntry {
+                program: ProgramCacheEntryType::Loaded(executable),
+                ..ProgramCacheEntry::default()
+            }),
+        );
+
+        let mut compute_units_consumed = 0;
+        let result =
+            invoke_context.process_instruction(&[], &[], &[0], &mut compute_units_consumed);
+        let program_timing = invoke_context
+            .timings
+            .per_program_timings
+            .remove(&PROGRAM_ID)
+            .unwrap();
+        (result, compute_units_consumed, program_timing)
+    }
+
+    #[test]
+    fn test_process_instruction_result() {
+        let (result, compute_units_consumed, program_timing) = process_program("mov64 r0, 0\nexit");
+        assert_eq!(result, Ok(()));
+        assert_eq!(compute_units_consumed, 2);
+        assert_eq!(program_timing.count.0, 1);
+        assert_eq!(program_timing.accumulated_units.0, 2);
+
+        let (result, compute_units_consumed, program_timing) =
+            process_program("mov64 r0, 42\nexit");
+        assert_eq!(result, Err(InstructionError::Custom(42)));
+        assert_eq!(program_timing.count.0, 0);
+        assert_eq!(
+            program_timing.errored_txs_compute_consumed,
+            vec![compute_units_consumed]
+        );
+    }
+
+    #[test]
+    fn test_process_instruction_error_mapping() {
+        // An InstructionError raised by a syscall is returned as is
+        let (result, _, _) = process_program("mov64 r1, 0\nsyscall fail\nexit");
+        assert_eq!(result, Err(InstructionError::InvalidAccountData));
+        // Any other syscall error, and any other VM error, fails the program
+        let (result, _, _) = process_program("mov64 r1, 1\nsyscall fail\nexit");
+        assert_eq!(result, Err(InstructionError::ProgramFailedToComplete));
+        let (result, _, _) = process_program("mov64 r0, 0\nldxdw r0, [r0+0]\nexit");
+        assert_eq!(result, Err(InstructionError::ProgramFailedToComplete));
+    }
+}
@@ -1,6 +1,300 @@
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use crate::transaction_context::Pubkey;
+
+const LOG_MESSAGES_BYTES_LIMIT: usize = 10 * 1000;
+
+/// Typed form of a log line, rendered to the legacy string by its `Display` impl
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogEvent {
+    /// "Program <address> invoke [<depth>]"
+    Invoke { program_id: Pubkey, depth: usize },
+    /// "Program log: <message>"
+    Log(String),
+    /// "Program data: <base64>*"
+    Data(Vec<Vec<u8>>),
+    /// "Program <address> consumed <units> of <limit> compute units"
+    ComputeUnitsConsumed {
+        program_id: Pubkey,
+        consumed: u64,
+        limit: u64,
+    },
+    /// "Program consumption: <units> units remaining"
+    ComputeUnitsRemaining(u64),
+    /// "Program return: <address> <base64>"
+    Return { program_id: Pubkey, data: Vec<u8> },
+    /// "Program <address> success"
+    Success { program_id: Pubkey },
+    /// "Program <address> failed: <error>"
+    Failure { program_id: Pubkey, error: String },
+    /// Any other runtime message, recorded verbatim
+    Message(String),
+    /// "Log truncated", recorded once when the byte limit is reached
+    Truncated,
+}
+
+impl fmt::Display for LogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogEvent::Invoke { program_id, depth } => write!(
+                f,
+                "Program {} invoke [{}]",
+                bs58::encode(program_id).into_string(),
+                depth
+            ),
+            LogEvent::Log(message) => write!(f, "Program log: {message}"),
+            LogEvent::Data(data) => write!(
+                f,
+                "Program data: {}",
+                data.iter()
+                    .map(|v| BASE64_STANDARD.encode(v))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            LogEvent::ComputeUnitsConsumed {
+                program_id,
+                consumed,
+                limit,
+            } => write!(
+                f,
+                "Program {} consumed {} of {} compute units",
+                bs58::encode(program_id).into_string(),
+                consumed,
+                limit
+            ),
+            LogEvent::ComputeUnitsRemaining(remaining) => {
+                write!(f, "Program consumption: {remaining} units remaining")
+            }
+            LogEvent::Return { program_id, data } => write!(
+                f,
+                "Program return: {} {}",
+                bs58::encode(program_id).into_string(),
+                BASE64_STANDARD.encode(data)
+            ),
+            LogEvent::Success { program_id } => write!(
+                f,
+                "Program {} success",
+                bs58::encode(program_id).into_string()
+            ),
+            LogEvent::Failure { program_id, error } => write!(
+                f,
+                "Program {} failed: {}",
+                bs58::encode(program_id).into_string(),
+                error
+            ),
+            LogEvent::Message(message) => f.write_str(message),
+            LogEvent::Truncated => f.write_str("Log truncated"),
+        }
+    }
+}
+
+/// Renders `events` into the legacy string log
+pub fn render_log_events(events: &[LogEvent]) -> Vec<String> {
+    events.iter().map(ToString::to_string).collect()
+}
+
 pub struct LogCollector {
     pub messages: Vec<String>,
+    pub events: Vec<LogEvent>,
     pub bytes_written: usize,
     pub bytes_limit: Option<usize>,
     pub limit_warning: bool,
-}
\ No newline at end of file
+}
+
+impl Default for LogCollector {
+    fn default() -> Self {
+        Self {
+            messages: Vec::new(),
+            events: Vec::new(),
+            bytes_written: 0,
+            bytes_limit: Some(LOG_MESSAGES_BYTES_LIMIT),
+            limit_warning: false,
+        }
+    }
+}
+
+impl LogCollector {
+    /// Records `message`, replacing everything past `bytes_limit` with a single "Log truncated"
+    pub fn log(&mut self, message: &str) {
+        self.log_event(LogEvent::Message(message.to_string()));
+    }
+
+    /// Records `event` and its rendered message under the same byte limit as [Self::log]
+    pub fn log_event(&mut self, event: LogEvent) {
+        let message = event.to_string();
+        let Some(limit) = self.bytes_limit else {
+            self.messages.push(message);
+            self.events.push(event);
+            return;
+        };
+
+        let bytes_written = self.bytes_written.saturating_add(message.len());
+        if bytes_written >= limit {
+            if !self.limit_warning {
+                self.limit_warning = true;
+                self.messages.push(LogEvent::Truncated.to_string());
+                self.events.push(LogEvent::Truncated);
+            }
+        } else {
+            self.bytes_written = bytes_written;
+            self.messages.push(message);
+            self.events.push(event);
+        }
+    }
+
+    pub fn get_recorded_content(&self) -> &[String] {
+        self.messages.as_slice()
+    }
+
+    pub fn get_recorded_events(&self) -> &[LogEvent] {
+        self.events.as_slice()
+    }
+
+    pub fn new_ref() -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self::default()))
+    }
+
+    pub fn new_ref_with_limit(bytes_limit: Option<usize>) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            bytes_limit,
+            ..Self::default()
+        }))
+    }
+
+    pub fn into_messages(self) -> Vec<String> {
+        self.messages
+    }
+
+    pub fn into_events(self) -> Vec<LogEvent> {
+        self.events
+    }
+}
+
+/// Convenience macro to log a message with an `Option<Rc<RefCell<LogCollector>>>`
+#[macro_export]
+macro_rules! ic_logger_msg {
+    ($log_collector:expr, $message:expr) => {
+        if let Some(log_collector) = $log_collector.as_ref() {
+            if let Ok(mut log_collector) = log_collector.try_borrow_mut() {
+                log_collector.log($message);
+            }
+        }
+    };
+    ($log_collector:expr, $fmt:expr, $($arg:tt)*) => {
+        if let Some(log_collector) = $log_collector.as_ref() {
+            if let Ok(mut log_collector) = log_collector.try_borrow_mut() {
+                log_collector.log(&format!($fmt, $($arg)*));
+            }
+        }
+    };
+}
+
+/// Convenience macro to record a [LogEvent] with an `Option<Rc<RefCell<LogCollector>>>`
+#[macro_export]
+macro_rules! ic_logger_event {
+    ($log_collector:expr, $event:expr) => {
+        if let Some(log_collector) = $log_collector.as_ref() {
+            if let Ok(mut log_collector) = log_collector.try_borrow_mut() {
+                log_collector.log_event($event);
+            }
+        }
+    };
+}
+
+/// Convenience macro to log a message with an `InvokeContext`
+#[macro_export]
+macro_rules! ic_msg {
+    ($invoke_context:expr, $message:expr) => {
+        $crate::ic_logger_msg!($invoke_context.get_log_collector(), $message)
+    };
+    ($invoke_context:expr, $fmt:expr, $($arg:tt)*) => {
+        $crate::ic_logger_msg!($invoke_context.get_log_collector(), $fmt, $($arg)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_truncated() {
+        let mut log_collector = LogCollector {
+            bytes_limit: Some(10),
+            ..LogCollector::default()
+        };
+        log_collector.log("12345");
+        log_collector.log("6789");
+        log_collector.log("abcde");
+        log_collector.log("fghij");
+        log_collector.log("klmno");
+
+        assert_eq!(
+            log_collector.get_recorded_content(),
+            ["12345", "6789", "Log truncated"]
+        );
+        assert_eq!(log_collector.bytes_written, 9);
+        assert!(log_collector.limit_warning);
+    }
+
+    #[test]
+    fn test_render_log_events() {
+        let program_id = [1; 32];
+        let events = vec![
+            LogEvent::Invoke {
+                program_id,
+                depth: 2,
+            },
+            LogEvent::Log("hello".to_string()),
+            LogEvent::Data(vec![vec![1, 2, 3], vec![], vec![255]]),
+            LogEvent::ComputeUnitsConsumed {
+                program_id,
+                consumed: 150,
+                limit: 200_000,
+            },
+            LogEvent::ComputeUnitsRemaining(199_850),
+            LogEvent::Return {
+                program_id,
+                data: vec![4, 5, 6],
+            },
+            LogEvent::Success { program_id },
+            LogEvent::Failure {
+                program_id,
+                error: "custom program error: 0x2a".to_string(),
+            },
+            LogEvent::Message("Program is not deployed".to_string()),
+        ];
+
+        let mut log_collector = LogCollector {
+            bytes_limit: Some(600),
+            ..LogCollector::default()
+        };
+        for event in events.iter().cloned() {
+            log_collector.log_event(event);
+        }
+        log_collector.log(&"x".repeat(600));
+        log_collector.log(&"x".repeat(600));
+
+        let mut expected_events = events;
+        expected_events.push(LogEvent::Truncated);
+        assert_eq!(log_collector.get_recorded_events(), expected_events);
+        assert_eq!(
+            log_collector.get_recorded_content(),
+            [
+                "Program 4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi invoke [2]",
+                "Program log: hello",
+                "Program data: AQID  /w==",
+                "Program 4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi consumed 150 of 200000 compute units",
+                "Program consumption: 199850 units remaining",
+                "Program return: 4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi BAUG",
+                "Program 4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi success",
+                "Program 4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi failed: custom program error: 0x2a",
+                "Program is not deployed",
+                "Log truncated",
+            ]
+        );
+        let messages = log_collector.get_recorded_content().to_vec();
+        assert_eq!(render_log_events(&log_collector.into_events()), messages);
+    }
+}
@@ -1,8 +1,141 @@
-use std::time::Instant;
+use std::{cell::Cell, fmt, rc::Rc};
+
+/// Source of monotonically non-decreasing ticks for [Measure].
+///
+/// Every timing in the crate goes through this trait so that the same code paths
+/// run on the host and inside the SP1 guest, where `std::time::Instant` is unavailable.
+pub trait Clock: fmt::Debug {
+    /// Current reading of the clock, in ticks.
+    fn now(&self) -> u64;
+    /// Notifies the clock that `ticks` units of work were performed.
+    ///
+    /// Called by the runtime whenever compute units are consumed. Wall clocks ignore it.
+    fn advance(&self, _ticks: u64) {}
+}
+
+/// Host wall clock, ticking in microseconds since its creation.
+#[cfg(not(target_os = "zkvm"))]
+#[derive(Debug, Clone, Copy)]
+pub struct WallClock {
+    origin: std::time::Instant,
+}
+
+#[cfg(not(target_os = "zkvm"))]
+impl Default for WallClock {
+    fn default() -> Self {
+        Self {
+            origin: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(not(target_os = "zkvm"))]
+impl Clock for WallClock {
+    fn now(&self) -> u64 {
+        self.origin.elapsed().as_micros() as u64
+    }
+}
+
+/// Deterministic clock that only moves when the runtime reports work through
+/// [Clock::advance], so every run of the same transaction reads the same ticks.
+#[derive(Debug, Default)]
+pub struct CycleClock {
+    ticks: Cell<u64>,
+}
+
+impl Clock for CycleClock {
+    fn now(&self) -> u64 {
+        self.ticks.get()
+    }
+
+    fn advance(&self, ticks: u64) {
+        self.ticks.set(self.ticks.get().saturating_add(ticks));
+    }
+}
+
+/// The wall clock on the host, the cycle clock inside the guest.
+pub fn default_clock() -> Rc<dyn Clock> {
+    #[cfg(not(target_os = "zkvm"))]
+    {
+        Rc::new(WallClock::default())
+    }
+    #[cfg(target_os = "zkvm")]
+    {
+        Rc::new(CycleClock::default())
+    }
+}
 
 #[derive(Debug)]
 pub struct Measure {
     name: &'static str,
-    start: Instant,
+    clock: Rc<dyn Clock>,
+    start: u64,
     duration: u64,
-}
\ No newline at end of file
+}
+
+impl Measure {
+    pub fn start(name: &'static str, clock: &Rc<dyn Clock>) -> Self {
+        Self {
+            name,
+            start: clock.now(),
+            clock: Rc::clone(clock),
+            duration: 0,
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.duration = self.elapsed();
+    }
+
+    /// Measured duration in clock ticks, microseconds for [WallClock].
+    pub fn as_ticks(&self) -> u64 {
+        self.duration
+    }
+
+    pub fn end_as_ticks(self) -> u64 {
+        self.elapsed()
+    }
+
+    fn elapsed(&self) -> u64 {
+        self.clock.now().saturating_sub(self.start)
+    }
+}
+
+impl fmt::Display for Measure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.duration == 0 {
+            write!(f, "{} running", self.name)
+        } else {
+            write!(f, "{} took {} ticks", self.name, self.duration)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_cycle_clock() {
+        let cycle_clock = Rc::new(CycleClock::default());
+        let clock: Rc<dyn Clock> = cycle_clock.clone();
+        clock.advance(5);
+
+        let mut measure = Measure::start("test", &clock);
+        assert_eq!(measure.to_string(), "test running");
+        clock.advance(100);
+        clock.advance(23);
+        measure.stop();
+        clock.advance(1000);
+        assert_eq!(measure.as_ticks(), 123);
+        assert_eq!(measure.to_string(), "test took 123 ticks");
+
+        let measure = Measure::start("test", &clock);
+        clock.advance(7);
+        assert_eq!(measure.end_as_ticks(), 7);
+        assert_eq!(cycle_clock.now(), 1135);
+
+        clock.advance(u64::MAX);
+        assert_eq!(cycle_clock.now(), u64::MAX);
+    }
+}
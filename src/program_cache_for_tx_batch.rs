@@ -1,122 +1,16 @@
-use solana_sbpf::{
-    program::BuiltinProgram,
-    elf::Executable};
-use std::{cell::RefCell, collections::{BTreeMap, HashMap}, pin::Pin, rc::Rc, sync::Arc};
+use solana_sbpf::{elf::Executable, program::BuiltinProgram};
+use std::{
+    collections::BTreeMap,
+    sync::{atomic::AtomicU64, Arc},
+};
 
-use crate::InvokeContext;
+use crate::{
+    transaction_context::{Epoch, Pubkey},
+    InvokeContext,
+};
 
 pub type ProgramRuntimeEnvironment = Arc<BuiltinProgram<InvokeContext<'static>>>;
-pub type Pubkey = [u8; 32];
-
-/// Syscall function without context
-pub type BuiltinFunction<C> = fn(*mut EbpfVm<C>, u64, u64, u64, u64, u64);
-
-#[repr(C)]
-pub struct EbpfVm<'a, C: ContextObject> {
-    /// Needed to exit from the guest back into the host
-    pub host_stack_pointer: *mut u64,
-    /// The current call depth.
-    ///
-    /// Incremented on calls and decremented on exits. It's used to enforce
-    /// config.max_call_depth and to know when to terminate execution.
-    pub call_depth: u64,
-    /// Pointer to ContextObject
-    pub context_object_pointer: &'a mut C,
-    /// Last return value of instruction_meter.get_remaining()
-    pub previous_instruction_meter: u64,
-    /// Outstanding value to instruction_meter.consume()
-    pub due_insn_count: u64,
-    /// CPU cycles accumulated by the stop watch
-    pub stopwatch_numerator: u64,
-    /// Number of times the stop watch was used
-    pub stopwatch_denominator: u64,
-    /// Registers inlined
-    pub registers: [u64; 12],
-    /// ProgramResult inlined
-    pub program_result: ProgramResult,
-    /// MemoryMapping inlined
-    pub memory_mapping: MemoryMapping<'a>,
-    /// Stack of CallFrames used by the Interpreter
-    pub call_frames: Vec<CallFrame>,
-    /// Loader built-in program
-    pub loader: Arc<BuiltinProgram<C>>,
-    /// TCP port for the debugger interface
-    #[cfg(feature = "debugger")]
-    pub debug_port: Option<u16>,
-}
-
-/// Runtime context
-pub trait ContextObject {
-    /// Called for every instruction executed when tracing is enabled
-    fn trace(&mut self, state: [u64; 12]);
-    /// Consume instructions from meter
-    fn consume(&mut self, amount: u64);
-    /// Get the number of remaining instructions allowed
-    fn get_remaining(&self) -> u64;
-}
-
-/// Represents the interface to a fixed functionality program
-#[derive(Eq)]
-pub struct BuiltinProgram<C: ContextObject> {
-    /// Holds the Config if this is a loader program
-    config: Option<Box<Config>>,
-    /// Function pointers by symbol with sparse indexing
-    sparse_registry: FunctionRegistry<BuiltinFunction<C>>,
-    /// Function pointers by symbol with dense indexing
-    dense_registry: FunctionRegistry<BuiltinFunction<C>>,
-}
-
-/// Holds the function symbols of an Executable
-#[derive(Debug, PartialEq, Eq)]
-pub struct FunctionRegistry<T> {
-    pub(crate) map: BTreeMap<u32, (Vec<u8>, T)>,
-}
-/// VM configuration settings
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Config {
-    /// Maximum call depth
-    pub max_call_depth: usize,
-    /// Size of a stack frame in bytes, must match the size specified in the LLVM BPF backend
-    pub stack_frame_size: usize,
-    /// Enables the use of MemoryMapping and MemoryRegion for address translation
-    pub enable_address_translation: bool,
-    /// Enables gaps in VM address space between the stack frames
-    pub enable_stack_frame_gaps: bool,
-    /// Maximal pc distance after which a new instruction meter validation is emitted by the JIT
-    pub instruction_meter_checkpoint_distance: usize,
-    /// Enable instruction meter and limiting
-    pub enable_instruction_meter: bool,
-    /// Enable instruction tracing
-    pub enable_instruction_tracing: bool,
-    /// Enable dynamic string allocation for labels
-    pub enable_symbol_and_section_labels: bool,
-    /// Reject ELF files containing issues that the verifier did not catch before (up to v0.2.21)
-    pub reject_broken_elfs: bool,
-    /// Ratio of native host instructions per random no-op in JIT (0 = OFF)
-    pub noop_instruction_rate: u32,
-    /// Enable disinfection of immediate values and offsets provided by the user in JIT
-    pub sanitize_user_provided_values: bool,
-    /// Avoid copying read only sections when possible
-    pub optimize_rodata: bool,
-    /// Use aligned memory mapping
-    pub aligned_memory_mapping: bool,
-    /// Allowed [SBPFVersion]s
-    pub enabled_sbpf_versions: std::ops::RangeInclusive<SBPFVersion>,
-}
-/// Defines a set of sbpf_version of an executable
-#[derive(Debug, PartialEq, PartialOrd, Eq, Clone, Copy)]
-pub enum SBPFVersion {
-    /// The legacy format
-    V0,
-    /// SIMD-0166
-    V1,
-    /// SIMD-0174, SIMD-0173
-    V2,
-    /// SIMD-0178, SIMD-0179, SIMD-0189
-    V3,
-    /// Used for future versions
-    Reserved,
-}
+pub type Slot = u64;
 
 /// The owner of a programs accounts, thus the loader of a program
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
@@ -150,7 +44,7 @@ pub enum ProgramCacheEntryType {
     /// A built-in program which is not stored on-chain but backed into and distributed with the validator
     Builtin(BuiltinProgram<InvokeContext<'static>>),
 }
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct ProgramCacheEntry {
     /// The program of this entry
     pub program: ProgramCacheEntryType,
@@ -178,13 +72,13 @@ pub struct ProgramRuntimeEnvironments {
     pub program_runtime_v2: ProgramRuntimeEnvironment,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone)]
 pub struct ProgramCacheForTxBatch {
     /// Pubkey is the address of a program.
     /// ProgramCacheEntry is the corresponding program entry valid for the slot in which a transaction is being executed.
-    entries: HashMap<Pubkey, Arc<ProgramCacheEntry>>,
+    entries: BTreeMap<Pubkey, Arc<ProgramCacheEntry>>,
     /// Program entries modified during the transaction batch.
-    modified_entries: HashMap<Pubkey, Arc<ProgramCacheEntry>>,
+    modified_entries: BTreeMap<Pubkey, Arc<ProgramCacheEntry>>,
     slot: Slot,
     pub environments: ProgramRuntimeEnvironments,
     /// Anticipated replacement for `environments` at the next epoch.
@@ -201,3 +95,101 @@ pub struct ProgramCacheForTxBatch {
     pub loaded_missing: bool,
     pub merged_modified: bool,
 }
+
+impl ProgramCacheEntry {
+    /// True if the entry is deployed on the current fork but not yet effective at `slot`.
+    pub fn is_implicit_delay_visibility_tombstone(&self, slot: Slot) -> bool {
+        !matches!(self.program, ProgramCacheEntryType::DelayVisibility)
+            && slot >= self.deployment_slot
+            && slot < self.effective_slot
+    }
+}
+
+impl ProgramCacheForTxBatch {
+    pub fn new(
+        slot: Slot,
+        environments: ProgramRuntimeEnvironments,
+        upcoming_environments: Option<ProgramRuntimeEnvironments>,
+        latest_root_epoch: Epoch,
+    ) -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            modified_entries: BTreeMap::new(),
+            slot,
+            environments,
+            upcoming_environments,
+            latest_root_epoch,
+            hit_max_limit: false,
+            loaded_missing: false,
+            merged_modified: false,
+        }
+    }
+
+    /// Returns the current environments depending on the given epoch
+    pub fn get_environments_for_epoch(&self, epoch: Epoch) -> &ProgramRuntimeEnvironments {
+        if epoch != self.latest_root_epoch {
+            if let Some(upcoming_environments) = self.upcoming_environments.as_ref() {
+                return upcoming_environments;
+            }
+        }
+        &self.environments
+    }
+
+    /// Refill the cache with a single entry, replacing the existing entry (if any).
+    /// Returns `true` if an entry existed, along with the newly inserted value.
+    pub fn replenish(
+        &mut self,
+        key: Pubkey,
+        entry: Arc<ProgramCacheEntry>,
+    ) -> (bool, Arc<ProgramCacheEntry>) {
+        (self.entries.insert(key, entry.clone()).is_some(), entry)
+    }
+
+    /// Store an entry in `modified_entries` for a program modified during the
+    /// transaction batch.
+    pub fn store_modified_entry(&mut self, key: Pubkey, entry: Arc<ProgramCacheEntry>) {
+        self.modified_entries.insert(key, entry);
+    }
+
+    /// Drain the program cache's modified entries, returning them in ascending key order.
+    pub fn drain_modified_entries(&mut self) -> BTreeMap<Pubkey, Arc<ProgramCacheEntry>> {
+        std::mem::take(&mut self.modified_entries)
+    }
+
+    pub fn find(&self, key: &Pubkey) -> Option<Arc<ProgramCacheEntry>> {
+        // Programs modified by the current transaction take precedence over
+        // the programs loaded for the transaction batch.
+        self.modified_entries
+            .get(key)
+            .or(self.entries.get(key))
+            .map(|entry| {
+                if entry.is_implicit_delay_visibility_tombstone(self.slot) {
+                    Arc::new(ProgramCacheEntry {
+                        program: ProgramCacheEntryType::DelayVisibility,
+                        account_owner: entry.account_owner,
+                        deployment_slot: entry.deployment_slot,
+                        effective_slot: entry.deployment_slot,
+                        ..ProgramCacheEntry::default()
+                    })
+                } else {
+                    entry.clone()
+                }
+            })
+    }
+
+    pub fn slot(&self) -> Slot {
+        self.slot
+    }
+
+    /// Merge entries modified by another transaction, in ascending key order.
+    pub fn merge(&mut self, modified_entries: &BTreeMap<Pubkey, Arc<ProgramCacheEntry>>) {
+        modified_entries.iter().for_each(|(key, entry)| {
+            self.merged_modified = true;
+            self.replenish(*key, entry.clone());
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
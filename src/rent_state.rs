@@ -0,0 +1,260 @@
+use crate::{
+    sysvar::Rent, transaction_context::Pubkey, AccountSharedData, IndexOfAccount, ReadableAccount,
+    TransactionContext, TransactionError, TransactionResult,
+};
+
+/// 1nc1nerator11111111111111111111111111111111
+pub const INCINERATOR_ID: Pubkey = [
+    0, 51, 144, 114, 141, 52, 17, 96, 121, 189, 201, 17, 191, 255, 0, 219, 212, 77, 46, 205, 204,
+    247, 156, 166, 225, 0, 56, 225, 0, 0, 0, 0,
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RentState {
+    /// account.lamports == 0
+    Uninitialized,
+    /// 0 < account.lamports < rent-exempt-minimum
+    RentPaying {
+        lamports: u64,    // account.lamports()
+        data_size: usize, // account.data().len()
+    },
+    /// account.lamports >= rent-exempt-minimum
+    RentExempt,
+}
+
+impl RentState {
+    /// Return a new RentState instance for a given account and rent.
+    pub fn from_account(account: &AccountSharedData, rent: &Rent) -> Self {
+        if account.lamports() == 0 {
+            Self::Uninitialized
+        } else if rent.is_exempt(account.lamports(), account.data().len()) {
+            Self::RentExempt
+        } else {
+            Self::RentPaying {
+                data_size: account.data().len(),
+                lamports: account.lamports(),
+            }
+        }
+    }
+
+    /// Check whether a transition from the pre_rent_state to this
+    /// state is valid.
+    pub fn transition_allowed_from(&self, pre_rent_state: &RentState) -> bool {
+        match self {
+            Self::Uninitialized | Self::RentExempt => true,
+            Self::RentPaying {
+                data_size: post_data_size,
+                lamports: post_lamports,
+            } => {
+                match pre_rent_state {
+                    Self::Uninitialized | Self::RentExempt => false,
+                    Self::RentPaying {
+                        data_size: pre_data_size,
+                        lamports: pre_lamports,
+                    } => {
+                        // Cannot remain RentPaying if resized or credited.
+                        post_data_size == pre_data_size && post_lamports <= pre_lamports
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Fails with [TransactionError::InsufficientFundsForRent] if the account moved into a
+/// rent-paying state it was not allowed to reach. The incinerator is exempt.
+pub fn check_rent_state(
+    pre_rent_state: Option<&RentState>,
+    post_rent_state: Option<&RentState>,
+    transaction_context: &TransactionContext,
+    index: IndexOfAccount,
+) -> TransactionResult<()> {
+    if let Some((pre_rent_state, post_rent_state)) = pre_rent_state.zip(post_rent_state) {
+        let address = transaction_context
+            .get_key_of_account_at_index(index)
+            .expect("account index in range");
+        if *address != INCINERATOR_ID && !post_rent_state.transition_allowed_from(pre_rent_state) {
+            return Err(TransactionError::InsufficientFundsForRent {
+                account_index: index as u8,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Rent state of every account of a transaction, captured before and after execution
+#[derive(Debug, PartialEq, Eq)]
+pub struct TransactionAccountStateInfo {
+    /// None for accounts which the transaction can not write to
+    rent_state: Option<RentState>,
+}
+
+impl TransactionAccountStateInfo {
+    /// Classifies the accounts for which `is_writable` holds
+    pub fn new(
+        transaction_context: &TransactionContext,
+        rent: &Rent,
+        is_writable: impl Fn(IndexOfAccount) -> bool,
+    ) -> Vec<Self> {
+        (0..transaction_context.get_number_of_accounts())
+            .map(|index| {
+                let rent_state = if is_writable(index) {
+                    let account = transaction_context
+                        .get_account_at_index(index)
+                        .expect("account index in range")
+                        .borrow();
+                    Some(RentState::from_account(&account, rent))
+                } else {
+                    None
+                };
+                Self { rent_state }
+            })
+            .collect()
+    }
+
+    /// Checks every account transition between `pre_state_infos` and `post_state_infos`
+    pub fn verify_changes(
+        pre_state_infos: &[Self],
+        post_state_infos: &[Self],
+        transaction_context: &TransactionContext,
+    ) -> TransactionResult<()> {
+        for (index, (pre_state_info, post_state_info)) in
+            pre_state_infos.iter().zip(post_state_infos).enumerate()
+        {
+            check_rent_state(
+                pre_state_info.rent_state.as_ref(),
+                post_state_info.rent_state.as_ref(),
+                transaction_context,
+                index as IndexOfAccount,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WritableAccount;
+
+    const OWNER: Pubkey = [9; 32];
+
+    fn rent_paying(lamports: u64, data_size: usize) -> RentState {
+        RentState::RentPaying {
+            lamports,
+            data_size,
+        }
+    }
+
+    #[test]
+    fn test_from_account() {
+        let rent = Rent::default();
+        let rent_exempt_minimum = rent.minimum_balance(10);
+        let account = |lamports| AccountSharedData::new(lamports, 10, &OWNER);
+
+        assert_eq!(
+            RentState::from_account(&account(0), &rent),
+            RentState::Uninitialized
+        );
+        assert_eq!(
+            RentState::from_account(&account(rent_exempt_minimum - 1), &rent),
+            rent_paying(rent_exempt_minimum - 1, 10)
+        );
+        assert_eq!(
+            RentState::from_account(&account(rent_exempt_minimum), &rent),
+            RentState::RentExempt
+        );
+    }
+
+    #[test]
+    fn test_transition_allowed_from() {
+        let pre_states = [
+            RentState::Uninitialized,
+            rent_paying(100, 10),
+            RentState::RentExempt,
+        ];
+
+        // Any state may become uninitialized or rent exempt
+        for pre_state in &pre_states {
+            assert!(RentState::Uninitialized.transition_allowed_from(pre_state));
+            assert!(RentState::RentExempt.transition_allowed_from(pre_state));
+        }
+
+        // Only rent paying accounts may stay rent paying
+        assert!(!rent_paying(100, 10).transition_allowed_from(&RentState::Uninitialized));
+        assert!(!rent_paying(100, 10).transition_allowed_from(&RentState::RentExempt));
+
+        // and only if neither resized nor credited
+        let pre_state = rent_paying(100, 10);
+        assert!(rent_paying(100, 10).transition_allowed_from(&pre_state));
+        assert!(rent_paying(50, 10).transition_allowed_from(&pre_state));
+        assert!(!rent_paying(100, 11).transition_allowed_from(&pre_state));
+        assert!(!rent_paying(100, 9).transition_allowed_from(&pre_state));
+        assert!(!rent_paying(101, 10).transition_allowed_from(&pre_state));
+    }
+
+    #[test]
+    fn test_verify_changes() {
+        let rent = Rent::default();
+        let rent_paying_lamports = rent.minimum_balance(0) - 1;
+        let transaction_context = TransactionContext::new(
+            vec![
+                ([1; 32], AccountSharedData::new(0, 0, &OWNER)),
+                (
+                    INCINERATOR_ID,
+                    AccountSharedData::new(rent_paying_lamports, 0, &OWNER),
+                ),
+                (
+                    [2; 32],
+                    AccountSharedData::new(rent_paying_lamports, 0, &OWNER),
+                ),
+            ],
+            rent.clone(),
+            1,
+            1,
+        );
+        let is_writable = |index| index != 2;
+        let pre_state_infos =
+            TransactionAccountStateInfo::new(&transaction_context, &rent, is_writable);
+
+        // Crediting the incinerator keeps it rent paying, which it is exempt from
+        transaction_context
+            .get_account_at_index(1)
+            .unwrap()
+            .borrow_mut()
+            .set_lamports(rent_paying_lamports + 1);
+        // Read-only accounts are not checked
+        transaction_context
+            .get_account_at_index(2)
+            .unwrap()
+            .borrow_mut()
+            .set_lamports(rent_paying_lamports + 1);
+        let post_state_infos =
+            TransactionAccountStateInfo::new(&transaction_context, &rent, is_writable);
+        assert_eq!(
+            TransactionAccountStateInfo::verify_changes(
+                &pre_state_infos,
+                &post_state_infos,
+                &transaction_context
+            ),
+            Ok(())
+        );
+
+        // Funding an uninitialized account below the rent exempt minimum is rejected
+        transaction_context
+            .get_account_at_index(0)
+            .unwrap()
+            .borrow_mut()
+            .set_lamports(1);
+        let post_state_infos =
+            TransactionAccountStateInfo::new(&transaction_context, &rent, is_writable);
+        assert_eq!(
+            TransactionAccountStateInfo::verify_changes(
+                &pre_state_infos,
+                &post_state_infos,
+                &transaction_context
+            ),
+            Err(TransactionError::InsufficientFundsForRent { account_index: 0 })
+        );
+    }
+}
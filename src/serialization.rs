@@ -0,0 +1,320 @@
+use solana_sbpf::{
+    aligned_memory::AlignedMemory,
+    ebpf::{HOST_ALIGN, MM_INPUT_START},
+};
+use std::mem::size_of;
+
+use crate::{
+    transaction_context::Pubkey, IndexOfAccount, InstructionContext, InstructionError,
+    ReadableAccount, SerializedAccountMetadata, TransactionContext, MAX_PERMITTED_DATA_LENGTH,
+};
+
+/// Maximum number of bytes a program may add to an account during a single realloc
+pub const MAX_PERMITTED_DATA_INCREASE: usize = 1_024 * 10;
+/// Alignment of u128 in the SBF target, account data is aligned to it
+pub const BPF_ALIGN_OF_U128: usize = 8;
+/// Value used to indicate that a serialized account is not a duplicate
+pub const NON_DUP_MARKER: u8 = u8::MAX;
+
+/// Maximum number of instruction accounts that can be serialized into the
+/// SBF VM.
+const MAX_INSTRUCTION_ACCOUNTS: u8 = NON_DUP_MARKER;
+
+/// Padding after the account data so that the next field is aligned to [BPF_ALIGN_OF_U128]
+fn align_offset(data_len: usize) -> usize {
+    (BPF_ALIGN_OF_U128 - data_len % BPF_ALIGN_OF_U128) % BPF_ALIGN_OF_U128
+}
+
+fn read_u64(buffer: &[u8], start: usize) -> Result<u64, InstructionError> {
+    buffer
+        .get(start..start.saturating_add(size_of::<u64>()))
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(InstructionError::InvalidArgument)
+}
+
+/// Serializes the current instruction into the aligned input region layout
+/// used by loader v2 and v3 programs, copying the account data.
+pub fn serialize_parameters(
+    transaction_context: &TransactionContext,
+    instruction_context: &InstructionContext,
+) -> Result<(AlignedMemory<HOST_ALIGN>, Vec<SerializedAccountMetadata>), InstructionError> {
+    let num_ix_accounts = instruction_context.get_number_of_instruction_accounts();
+    if num_ix_accounts > MAX_INSTRUCTION_ACCOUNTS as IndexOfAccount {
+        return Err(InstructionError::MaxAccountsExceeded);
+    }
+    let program_id = *instruction_context.get_last_program_key(transaction_context)?;
+    let instruction_data = instruction_context.get_instruction_data();
+
+    let mut buffer = Vec::new();
+    let mut accounts_metadata: Vec<SerializedAccountMetadata> =
+        Vec::with_capacity(num_ix_accounts as usize);
+    let vm_addr = |buffer: &Vec<u8>| MM_INPUT_START.saturating_add(buffer.len() as u64);
+
+    buffer.extend_from_slice(&(num_ix_accounts as u64).to_le_bytes());
+    for instruction_account_index in 0..num_ix_accounts {
+        if let Some(position) =
+            instruction_context.is_instruction_account_duplicate(instruction_account_index)?
+        {
+            let metadata = accounts_metadata
+                .get(position as usize)
+                .ok_or(InstructionError::NotEnoughAccountKeys)?
+                .clone();
+            accounts_metadata.push(metadata);
+            buffer.push(position as u8);
+            buffer.extend_from_slice(&[0u8; 7]);
+            continue;
+        }
+        let index_in_transaction = instruction_context
+            .get_index_of_instruction_account_in_transaction(instruction_account_index)?;
+        let account = transaction_context
+            .get_account_at_index(index_in_transaction)?
+            .try_borrow()
+            .map_err(|_| InstructionError::AccountBorrowFailed)?;
+        let key = transaction_context.get_key_of_account_at_index(index_in_transaction)?;
+        let data_len = account.data().len();
+
+        buffer.push(NON_DUP_MARKER);
+        buffer.push(
+            instruction_context.is_instruction_account_signer(instruction_account_index)? as u8,
+        );
+        buffer.push(
+            instruction_context.is_instruction_account_writable(instruction_account_index)? as u8,
+        );
+        buffer.push(account.executable() as u8);
+        buffer.extend_from_slice(&[0u8; 4]);
+        let vm_key_addr = vm_addr(&buffer);
+        buffer.extend_from_slice(key);
+        let vm_owner_addr = vm_addr(&buffer);
+        buffer.extend_from_slice(account.owner());
+        let vm_lamports_addr = vm_addr(&buffer);
+        buffer.extend_from_slice(&account.lamports().to_le_bytes());
+        buffer.extend_from_slice(&(data_len as u64).to_le_bytes());
+        let vm_data_addr = vm_addr(&buffer);
+        buffer.extend_from_slice(account.data());
+        buffer.resize(
+            buffer.len() + MAX_PERMITTED_DATA_INCREASE + align_offset(data_len),
+            0,
+        );
+        buffer.extend_from_slice(&account.rent_epoch().to_le_bytes());
+        accounts_metadata.push(SerializedAccountMetadata {
+            original_data_len: data_len,
+            vm_data_addr,
+            vm_key_addr,
+            vm_lamports_addr,
+            vm_owner_addr,
+        });
+    }
+    buffer.extend_from_slice(&(instruction_data.len() as u64).to_le_bytes());
+    buffer.extend_from_slice(instruction_data);
+    buffer.extend_from_slice(&program_id);
+
+    Ok((AlignedMemory::from_slice(&buffer), accounts_metadata))
+}
+
+/// Writes the lamports, data and owner left in the input region by the program
+/// back into the accounts of the current instruction.
+pub fn deserialize_parameters(
+    transaction_context: &TransactionContext,
+    instruction_context: &InstructionContext,
+    buffer: &[u8],
+    accounts_metadata: &[SerializedAccountMetadata],
+) -> Result<(), InstructionError> {
+    let mut start = size_of::<u64>(); // number of accounts
+    for (instruction_account_index, metadata) in
+        (0..instruction_context.get_number_of_instruction_accounts()).zip(accounts_metadata.iter())
+    {
+        let pre_len = metadata.original_data_len;
+        start += size_of::<u8>(); // position
+        if instruction_context
+            .is_instruction_account_duplicate(instruction_account_index)?
+            .is_some()
+        {
+            start += 7; // padding to 64-bit aligned
+            continue;
+        }
+        let mut borrowed_account = instruction_context
+            .try_borrow_instruction_account(transaction_context, instruction_account_index)?;
+        start += size_of::<u8>() // is_signer
+            + size_of::<u8>() // is_writable
+            + size_of::<u8>() // executable
+            + size_of::<u32>() // original_data_len
+            + size_of::<Pubkey>(); // key
+        let owner: Pubkey = buffer
+            .get(start..start + size_of::<Pubkey>())
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(InstructionError::InvalidArgument)?;
+        start += size_of::<Pubkey>(); // owner
+        let lamports = read_u64(buffer, start)?;
+        if borrowed_account.get_lamports() != lamports {
+            borrowed_account.set_lamports(lamports)?;
+        }
+        start += size_of::<u64>(); // lamports
+        let post_len = read_u64(buffer, start)? as usize;
+        start += size_of::<u64>(); // data length
+        if post_len.saturating_sub(pre_len) > MAX_PERMITTED_DATA_INCREASE
+            || post_len > MAX_PERMITTED_DATA_LENGTH as usize
+        {
+            return Err(InstructionError::InvalidRealloc);
+        }
+        let data = buffer
+            .get(start..start.saturating_add(post_len))
+            .ok_or(InstructionError::InvalidArgument)?;
+        match borrowed_account
+            .can_data_be_resized(post_len)
+            .and_then(|_| borrowed_account.can_data_be_changed())
+        {
+            Ok(()) => borrowed_account.set_data_from_slice(data)?,
+            Err(err) if borrowed_account.get_data() != data => return Err(err),
+            _ => {}
+        }
+        start += pre_len + MAX_PERMITTED_DATA_INCREASE + align_offset(pre_len);
+        start += size_of::<u64>(); // rent_epoch
+        if *borrowed_account.get_owner() != owner {
+            // Change the owner at the end so that we are allowed to change the lamports and data before
+            borrowed_account.set_owner(&owner)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sysvar::Rent, AccountSharedData, InstructionAccount, WritableAccount};
+
+    const PROGRAM_ID: Pubkey = [1; 32];
+    /// Offset of the data length of the first serialized account, after the number of
+    /// accounts, the flags, the key, the owner and the lamports
+    const DATA_LEN_OFFSET: usize = 88;
+
+    fn account(lamports: u64, data: &[u8]) -> AccountSharedData {
+        let mut account = AccountSharedData::default();
+        account.set_lamports(lamports);
+        account.set_owner(PROGRAM_ID);
+        account.set_data_from_slice(data);
+        account
+    }
+
+    /// An instruction of the program over a writable account, a readonly account and the
+    /// writable account again
+    fn transaction_context() -> TransactionContext {
+        let mut transaction_context = TransactionContext::new(
+            vec![
+                (PROGRAM_ID, AccountSharedData::default()),
+                ([3; 32], account(100, &[1, 2, 3])),
+                ([4; 32], account(200, &[4; 9])),
+            ],
+            Rent::default(),
+            1,
+            1,
+        );
+        let instruction_accounts = [(1, 0, true), (2, 1, false), (1, 0, true)].map(
+            |(index_in_transaction, index_in_callee, is_writable)| InstructionAccount {
+                index_in_transaction,
+                index_in_caller: index_in_transaction,
+                index_in_callee,
+                is_signer: false,
+                is_writable,
+            },
+        );
+        transaction_context
+            .get_next_instruction_context()
+            .unwrap()
+            .configure(&[0], &instruction_accounts, &[7, 8]);
+        transaction_context.push().unwrap();
+        transaction_context
+    }
+
+    #[test]
+    fn test_serialize_parameters_round_trip() {
+        let transaction_context = transaction_context();
+        let instruction_context = transaction_context
+            .get_current_instruction_context()
+            .unwrap();
+        let (mut buffer, accounts_metadata) =
+            serialize_parameters(&transaction_context, instruction_context).unwrap();
+
+        let serialized = buffer.as_slice();
+        assert_eq!(read_u64(serialized, 0), Ok(3));
+        assert_eq!(&serialized[16..48], &[3; 32]);
+        assert_eq!(&serialized[48..80], &PROGRAM_ID);
+        assert_eq!(read_u64(serialized, 80), Ok(100));
+        assert_eq!(read_u64(serialized, DATA_LEN_OFFSET), Ok(3));
+        assert_eq!(&serialized[96..99], &[1, 2, 3]);
+        // The duplicate is serialized as the position of its first occurrence
+        assert_eq!(accounts_metadata.len(), 3);
+        assert_eq!(
+            accounts_metadata[2].vm_data_addr,
+            accounts_metadata[0].vm_data_addr
+        );
+        let duplicate_offset = (accounts_metadata[1].vm_data_addr - MM_INPUT_START) as usize
+            + 9
+            + MAX_PERMITTED_DATA_INCREASE
+            + align_offset(9)
+            + size_of::<u64>();
+        assert_eq!(&serialized[duplicate_offset..duplicate_offset + 8], &[0; 8]);
+        let len = serialized.len();
+        assert_eq!(read_u64(serialized, duplicate_offset + 8), Ok(2));
+        assert_eq!(&serialized[len - 34..len - 32], &[7, 8]);
+        assert_eq!(&serialized[len - 32..], &PROGRAM_ID);
+
+        // The program moves lamports and grows the account as much as allowed
+        let new_len = 3 + MAX_PERMITTED_DATA_INCREASE;
+        let serialized = buffer.as_slice_mut();
+        serialized[80..88].copy_from_slice(&50u64.to_le_bytes());
+        serialized[DATA_LEN_OFFSET..DATA_LEN_OFFSET + 8]
+            .copy_from_slice(&(new_len as u64).to_le_bytes());
+        serialized[99..96 + new_len].fill(5);
+        deserialize_parameters(
+            &transaction_context,
+            instruction_context,
+            buffer.as_slice(),
+            &accounts_metadata,
+        )
+        .unwrap();
+
+        let account = transaction_context
+            .get_account_at_index(1)
+            .unwrap()
+            .borrow();
+        assert_eq!(account.lamports(), 50);
+        assert_eq!(account.data().len(), new_len);
+        assert_eq!(&account.data()[..3], &[1, 2, 3]);
+        assert!(account.data()[3..].iter().all(|byte| *byte == 5));
+        let account = transaction_context
+            .get_account_at_index(2)
+            .unwrap()
+            .borrow();
+        assert_eq!(account.lamports(), 200);
+        assert_eq!(account.data(), &[4; 9]);
+    }
+
+    #[test]
+    fn test_deserialize_parameters_invalid_realloc() {
+        let transaction_context = transaction_context();
+        let instruction_context = transaction_context
+            .get_current_instruction_context()
+            .unwrap();
+        let (mut buffer, accounts_metadata) =
+            serialize_parameters(&transaction_context, instruction_context).unwrap();
+
+        let new_len = 3 + MAX_PERMITTED_DATA_INCREASE + 1;
+        buffer.as_slice_mut()[DATA_LEN_OFFSET..DATA_LEN_OFFSET + 8]
+            .copy_from_slice(&(new_len as u64).to_le_bytes());
+        assert_eq!(
+            deserialize_parameters(
+                &transaction_context,
+                instruction_context,
+                buffer.as_slice(),
+                &accounts_metadata,
+            ),
+            Err(InstructionError::InvalidRealloc)
+        );
+        let account = transaction_context
+            .get_account_at_index(1)
+            .unwrap()
+            .borrow();
+        assert_eq!(account.data(), &[1, 2, 3]);
+    }
+}
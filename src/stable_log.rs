@@ -0,0 +1,144 @@
+//! Stable program log messages
+//!
+//! The format of these log messages should not be modified to avoid breaking downstream consumers
+//! of program logging
+//!
+//! Each line is recorded as a [LogEvent], so the collector keeps the typed event next to the
+//! rendered string.
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use crate::{ic_logger_event, transaction_context::Pubkey, LogCollector, LogEvent};
+
+/// Log a program invoke.
+///
+/// The general form is:
+///
+/// ```notrust
+/// "Program <address> invoke [<depth>]"
+/// ```
+pub fn program_invoke(
+    log_collector: &Option<Rc<RefCell<LogCollector>>>,
+    program_id: &Pubkey,
+    invoke_depth: usize,
+) {
+    ic_logger_event!(
+        log_collector,
+        LogEvent::Invoke {
+            program_id: *program_id,
+            depth: invoke_depth,
+        }
+    );
+}
+
+/// Log a message from the program itself.
+///
+/// The general form is:
+///
+/// ```notrust
+/// "Program log: <program-generated output>"
+/// ```
+///
+/// That is, any program-generated output is guaranteed to be prefixed by "Program log: "
+pub fn program_log(log_collector: &Option<Rc<RefCell<LogCollector>>>, message: &str) {
+    ic_logger_event!(log_collector, LogEvent::Log(message.to_string()));
+}
+
+/// Emit a program data.
+///
+/// The general form is:
+///
+/// ```notrust
+/// "Program data: <binary-data-in-base64>*"
+/// ```
+///
+/// That is, any program-generated output is guaranteed to be prefixed by "Program data: "
+pub fn program_data(log_collector: &Option<Rc<RefCell<LogCollector>>>, data: &[&[u8]]) {
+    ic_logger_event!(
+        log_collector,
+        LogEvent::Data(data.iter().map(|v| v.to_vec()).collect())
+    );
+}
+
+/// Log the compute units consumed by a program.
+///
+/// The general form is:
+///
+/// ```notrust
+/// "Program <address> consumed <units> of <remaining-before-invoke> compute units"
+/// ```
+pub fn program_consumed(
+    log_collector: &Option<Rc<RefCell<LogCollector>>>,
+    program_id: &Pubkey,
+    consumed: u64,
+    limit: u64,
+) {
+    ic_logger_event!(
+        log_collector,
+        LogEvent::ComputeUnitsConsumed {
+            program_id: *program_id,
+            consumed,
+            limit,
+        }
+    );
+}
+
+/// Log return data as from the program itself. This line will not be present if no return
+/// data was set, or if the return data was set to zero length.
+///
+/// The general form is:
+///
+/// ```notrust
+/// "Program return: <program-id> <program-generated-data-in-base64>"
+/// ```
+///
+/// That is, any program-generated output is guaranteed to be prefixed by "Program return: "
+pub fn program_return(
+    log_collector: &Option<Rc<RefCell<LogCollector>>>,
+    program_id: &Pubkey,
+    data: &[u8],
+) {
+    ic_logger_event!(
+        log_collector,
+        LogEvent::Return {
+            program_id: *program_id,
+            data: data.to_vec(),
+        }
+    );
+}
+
+/// Log successful program execution.
+///
+/// The general form is:
+///
+/// ```notrust
+/// "Program <address> success"
+/// ```
+pub fn program_success(log_collector: &Option<Rc<RefCell<LogCollector>>>, program_id: &Pubkey) {
+    ic_logger_event!(
+        log_collector,
+        LogEvent::Success {
+            program_id: *program_id,
+        }
+    );
+}
+
+/// Log program execution failure
+///
+/// The general form is:
+///
+/// ```notrust
+/// "Program <address> failed: <program error details>"
+/// ```
+pub fn program_failure<E: fmt::Display + ?Sized>(
+    log_collector: &Option<Rc<RefCell<LogCollector>>>,
+    program_id: &Pubkey,
+    err: &E,
+) {
+    ic_logger_event!(
+        log_collector,
+        LogEvent::Failure {
+            program_id: *program_id,
+            error: err.to_string(),
+        }
+    );
+}
@@ -1,4 +1,5 @@
-
+use solana_sbpf::ebpf::MM_HEAP_START;
+use std::alloc::Layout;
 
 #[derive(Debug, Clone)]
 pub struct SerializedAccountMetadata {
@@ -14,8 +15,32 @@ pub struct BpfAllocator {
     pos: u64,
 }
 
+impl BpfAllocator {
+    pub fn new(len: u64) -> Self {
+        Self { len, pos: 0 }
+    }
+
+    /// Bump-allocates `layout` in the heap region, returning its VM address
+    pub fn alloc(&mut self, layout: Layout) -> Option<u64> {
+        let bytes_to_align = (self.pos as *const u8).align_offset(layout.align()) as u64;
+        if self
+            .pos
+            .saturating_add(bytes_to_align)
+            .saturating_add(layout.size() as u64)
+            <= self.len
+        {
+            self.pos = self.pos.saturating_add(bytes_to_align);
+            let addr = MM_HEAP_START.saturating_add(self.pos);
+            self.pos = self.pos.saturating_add(layout.size() as u64);
+            Some(addr)
+        } else {
+            None
+        }
+    }
+}
+
 pub struct SyscallContext {
     pub allocator: BpfAllocator,
     pub accounts_metadata: Vec<SerializedAccountMetadata>,
     pub trace_log: Vec<[u64; 12]>,
-}
\ No newline at end of file
+}
@@ -0,0 +1,416 @@
+#[cfg(not(all(feature = "sp1", target_os = "zkvm")))]
+use sha2::{Digest, Sha256 as Sha256Backend};
+#[cfg(not(all(feature = "sp1", target_os = "zkvm")))]
+use sha3::Keccak256 as Keccak256Backend;
+
+#[cfg(all(feature = "sp1", target_os = "zkvm"))]
+use self::sp1::{Keccak256 as Keccak256Backend, Sha256 as Sha256Backend};
+
+use super::*;
+use crate::{ic_msg, HASH_BYTES};
+
+/// Hash function exposed to programs through [SyscallHash]
+pub trait HasherImpl {
+    const NAME: &'static str;
+
+    fn create_hasher() -> Self;
+    fn hash(&mut self, val: &[u8]);
+    fn result(self) -> [u8; HASH_BYTES];
+}
+
+pub struct Sha256Hasher(Sha256Backend);
+pub struct Blake3Hasher(blake3::Hasher);
+pub struct Keccak256Hasher(Keccak256Backend);
+
+impl HasherImpl for Sha256Hasher {
+    const NAME: &'static str = "Sha256";
+
+    fn create_hasher() -> Self {
+        Sha256Hasher(Sha256Backend::default())
+    }
+
+    fn hash(&mut self, val: &[u8]) {
+        self.0.update(val);
+    }
+
+    fn result(self) -> [u8; HASH_BYTES] {
+        self.0.finalize().into()
+    }
+}
+
+impl HasherImpl for Blake3Hasher {
+    const NAME: &'static str = "Blake3";
+
+    fn create_hasher() -> Self {
+        Blake3Hasher(blake3::Hasher::new())
+    }
+
+    fn hash(&mut self, val: &[u8]) {
+        self.0.update(val);
+    }
+
+    fn result(self) -> [u8; HASH_BYTES] {
+        self.0.finalize().into()
+    }
+}
+
+impl HasherImpl for Keccak256Hasher {
+    const NAME: &'static str = "Keccak256";
+
+    fn create_hasher() -> Self {
+        Keccak256Hasher(Keccak256Backend::default())
+    }
+
+    fn hash(&mut self, val: &[u8]) {
+        self.0.update(val);
+    }
+
+    fn result(self) -> [u8; HASH_BYTES] {
+        self.0.finalize().into()
+    }
+}
+
+/// SHA-256 and Keccak-256 built on the SP1 precompiles, which are far cheaper in cycles than
+/// running the compression functions as guest code. Blake3 has no precompile.
+#[cfg(any(test, all(feature = "sp1", target_os = "zkvm")))]
+mod sp1 {
+    #[cfg(not(all(feature = "sp1", target_os = "zkvm")))]
+    use self::host::{syscall_keccak_permute, syscall_sha256_compress, syscall_sha256_extend};
+    use crate::HASH_BYTES;
+    #[cfg(all(feature = "sp1", target_os = "zkvm"))]
+    use sp1_lib::{syscall_keccak_permute, syscall_sha256_compress, syscall_sha256_extend};
+
+    const SHA256_BLOCK_SIZE: usize = 64;
+    const SHA256_IV: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    /// Rate of Keccak-256, i.e. 1600 bits minus twice the 256 bit capacity
+    const KECCAK256_RATE: usize = 136;
+
+    pub struct Sha256 {
+        state: [u32; 8],
+        buffer: [u8; SHA256_BLOCK_SIZE],
+        buffer_len: usize,
+        total_len: u64,
+    }
+
+    impl Default for Sha256 {
+        fn default() -> Self {
+            Self {
+                state: SHA256_IV,
+                buffer: [0; SHA256_BLOCK_SIZE],
+                buffer_len: 0,
+                total_len: 0,
+            }
+        }
+    }
+
+    impl Sha256 {
+        pub fn update(&mut self, mut data: &[u8]) {
+            self.total_len = self.total_len.wrapping_add(data.len() as u64);
+            if self.buffer_len > 0 {
+                let take = (SHA256_BLOCK_SIZE - self.buffer_len).min(data.len());
+                self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&data[..take]);
+                self.buffer_len += take;
+                data = &data[take..];
+                if self.buffer_len < SHA256_BLOCK_SIZE {
+                    return;
+                }
+                let block = self.buffer;
+                self.compress(&block);
+                self.buffer_len = 0;
+            }
+            let mut blocks = data.chunks_exact(SHA256_BLOCK_SIZE);
+            for block in &mut blocks {
+                self.compress(block);
+            }
+            let rest = blocks.remainder();
+            self.buffer[..rest.len()].copy_from_slice(rest);
+            self.buffer_len = rest.len();
+        }
+
+        pub fn finalize(mut self) -> [u8; HASH_BYTES] {
+            let bit_len = self.total_len.wrapping_mul(8);
+            let mut padding = [0u8; SHA256_BLOCK_SIZE];
+            padding[0] = 0x80;
+            let padding_len = if self.buffer_len < 56 {
+                56 - self.buffer_len
+            } else {
+                120 - self.buffer_len
+            };
+            self.update(&padding[..padding_len]);
+            self.update(&bit_len.to_be_bytes());
+
+            let mut hash = [0u8; HASH_BYTES];
+            for (bytes, word) in hash.chunks_exact_mut(4).zip(self.state) {
+                bytes.copy_from_slice(&word.to_be_bytes());
+            }
+            hash
+        }
+
+        fn compress(&mut self, block: &[u8]) {
+            let mut w = [0u32; 64];
+            for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+                *word = u32::from_be_bytes(bytes.try_into().unwrap());
+            }
+            unsafe {
+                syscall_sha256_extend(&mut w);
+                syscall_sha256_compress(&mut w, &mut self.state);
+            }
+        }
+    }
+
+    pub struct Keccak256 {
+        state: [u64; 25],
+        buffer: [u8; KECCAK256_RATE],
+        buffer_len: usize,
+    }
+
+    impl Default for Keccak256 {
+        fn default() -> Self {
+            Self {
+                state: [0; 25],
+                buffer: [0; KECCAK256_RATE],
+                buffer_len: 0,
+            }
+        }
+    }
+
+    impl Keccak256 {
+        pub fn update(&mut self, mut data: &[u8]) {
+            while !data.is_empty() {
+                let take = (KECCAK256_RATE - self.buffer_len).min(data.len());
+                self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&data[..take]);
+                self.buffer_len += take;
+                data = &data[take..];
+                if self.buffer_len == KECCAK256_RATE {
+                    self.absorb_buffer();
+                }
+            }
+        }
+
+        pub fn finalize(mut self) -> [u8; HASH_BYTES] {
+            self.buffer[self.buffer_len..].fill(0);
+            self.buffer[self.buffer_len] ^= 0x01;
+            self.buffer[KECCAK256_RATE - 1] ^= 0x80;
+            self.absorb_buffer();
+
+            let mut hash = [0u8; HASH_BYTES];
+            for (bytes, lane) in hash.chunks_exact_mut(8).zip(self.state) {
+                bytes.copy_from_slice(&lane.to_le_bytes());
+            }
+            hash
+        }
+
+        fn absorb_buffer(&mut self) {
+            for (lane, bytes) in self.state.iter_mut().zip(self.buffer.chunks_exact(8)) {
+                *lane ^= u64::from_le_bytes(bytes.try_into().unwrap());
+            }
+            unsafe {
+                syscall_keccak_permute(&mut self.state);
+            }
+            self.buffer_len = 0;
+        }
+    }
+
+    /// Software versions of the precompiles, so the block and padding logic above can be
+    /// checked against `sha2` and `sha3` off the zkVM
+    #[cfg(not(all(feature = "sp1", target_os = "zkvm")))]
+    mod host {
+        pub unsafe fn syscall_sha256_extend(w: *mut [u32; 64]) {
+            let w = &mut *w;
+            for i in 16..64 {
+                let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+                let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+                w[i] = w[i - 16]
+                    .wrapping_add(s0)
+                    .wrapping_add(w[i - 7])
+                    .wrapping_add(s1);
+            }
+        }
+
+        pub unsafe fn syscall_sha256_compress(w: *mut [u32; 64], state: *mut [u32; 8]) {
+            // The rounds only read the message schedule, which the first 16 words determine
+            let mut block = [0u8; 64];
+            for (bytes, word) in block.chunks_exact_mut(4).zip(&(&*w)[..16]) {
+                bytes.copy_from_slice(&word.to_be_bytes());
+            }
+            sha2::compress256(&mut *state, &[block.into()]);
+        }
+
+        pub unsafe fn syscall_keccak_permute(state: *mut [u64; 25]) {
+            keccak::f1600(&mut *state);
+        }
+    }
+}
+
+declare_builtin_function!(
+    /// Generic Hashing Syscall
+    SyscallHash<H: HasherImpl>,
+    fn rust(
+        invoke_context: &mut InvokeContext,
+        vals_addr: u64,
+        vals_len: u64,
+        result_addr: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Error> {
+        let compute_budget = invoke_context.get_compute_budget();
+        if compute_budget.sha256_max_slices < vals_len {
+            ic_msg!(
+                invoke_context,
+                "{} Hashing {} sequences in one syscall is over the limit {}",
+                H::NAME,
+                vals_len,
+                compute_budget.sha256_max_slices,
+            );
+            return Err(SyscallError::TooManySlices.into());
+        }
+
+        consume_compute_meter(invoke_context, compute_budget.sha256_base_cost)?;
+
+        let hash_result = translate_slice_mut::<u8>(
+            memory_mapping,
+            result_addr,
+            HASH_BYTES as u64,
+            invoke_context.get_check_aligned(),
+        )?;
+        let mut hasher = H::create_hasher();
+        if vals_len > 0 {
+            let vals = translate_slice::<VmSlice>(
+                memory_mapping,
+                vals_addr,
+                vals_len,
+                invoke_context.get_check_aligned(),
+            )?;
+            for val in vals.iter() {
+                let bytes = translate_slice::<u8>(
+                    memory_mapping,
+                    val.addr,
+                    val.len,
+                    invoke_context.get_check_aligned(),
+                )?;
+                let cost = compute_budget
+                    .sha256_slice_cost(val.len)
+                    .unwrap_or(u64::MAX);
+                consume_compute_meter(invoke_context, cost)?;
+                hasher.hash(bytes);
+            }
+        }
+        hash_result.copy_from_slice(&hasher.result());
+        Ok(0)
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::with_mock_invoke_context;
+    use solana_sbpf::{
+        aligned_memory::AlignedMemory,
+        ebpf::{HOST_ALIGN, MM_INPUT_START},
+        memory_region::MemoryRegion,
+        program::SBPFVersion,
+    };
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    /// Runs [SyscallHash] over `vals`, laid out in the input region the way a program passes them
+    fn syscall_hash<H: HasherImpl>(vals: &[&[u8]]) -> [u8; HASH_BYTES] {
+        let data_start = vals.len() * size_of::<VmSlice>();
+        let mut input = Vec::new();
+        let mut data = Vec::new();
+        for val in vals {
+            let addr = MM_INPUT_START + (data_start + data.len()) as u64;
+            input.extend_from_slice(&addr.to_le_bytes());
+            input.extend_from_slice(&(val.len() as u64).to_le_bytes());
+            data.extend_from_slice(val);
+        }
+        input.extend_from_slice(&data);
+        let result_offset = input.len();
+        input.resize(result_offset + HASH_BYTES, 0);
+        let mut input = AlignedMemory::<HOST_ALIGN>::from_slice(&input);
+
+        with_mock_invoke_context!(invoke_context, transaction_context, Vec::new());
+
+        let config = Config {
+            aligned_memory_mapping: false,
+            ..Config::default()
+        };
+        let mut memory_mapping = MemoryMapping::new(
+            vec![MemoryRegion::new_writable(
+                input.as_slice_mut(),
+                MM_INPUT_START,
+            )],
+            &config,
+            SBPFVersion::V3,
+        )
+        .unwrap();
+        let result = SyscallHash::rust::<H>(
+            &mut invoke_context,
+            MM_INPUT_START,
+            vals.len() as u64,
+            MM_INPUT_START + result_offset as u64,
+            0,
+            0,
+            &mut memory_mapping,
+        )
+        .unwrap();
+        assert_eq!(result, 0);
+        input.as_slice()[result_offset..].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_syscall_hash_slices() {
+        let vals: [&[u8]; 3] = [b"hello", b"", b" world"];
+
+        assert_eq!(
+            hex(&syscall_hash::<Sha256Hasher>(&vals)),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+        assert_eq!(
+            hex(&syscall_hash::<Keccak256Hasher>(&vals)),
+            "47173285a8d7341e5e972fc677286384f802f8ef42a5ec5f03bbfa254cb01fad"
+        );
+        assert_eq!(
+            hex(&syscall_hash::<Blake3Hasher>(&vals)),
+            "d74981efa70a0c880b8d8c1985d075dbcbf679b99a5f9914e5aaf96b831a9e24"
+        );
+    }
+
+    #[test]
+    fn test_sp1_hashers() {
+        let data = (0..=255).cycle().take(300).collect::<Vec<u8>>();
+        // Cover every buffer fill level, including lengths straddling the padding boundaries
+        for len in 0..data.len() {
+            let data = &data[..len];
+            let expected_sha256: [u8; HASH_BYTES] = sha2::Sha256::digest(data).into();
+            let expected_keccak256: [u8; HASH_BYTES] = sha3::Keccak256::digest(data).into();
+            for split in [0, len / 3, len / 2, len] {
+                let (head, tail) = data.split_at(split);
+
+                let mut sha256 = sp1::Sha256::default();
+                sha256.update(head);
+                sha256.update(tail);
+                assert_eq!(
+                    sha256.finalize(),
+                    expected_sha256,
+                    "len {len} split {split}"
+                );
+
+                let mut keccak256 = sp1::Keccak256::default();
+                keccak256.update(head);
+                keccak256.update(tail);
+                assert_eq!(
+                    keccak256.finalize(),
+                    expected_keccak256,
+                    "len {len} split {split}"
+                );
+            }
+        }
+    }
+}
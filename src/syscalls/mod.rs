@@ -0,0 +1,538 @@
+use solana_sbpf::{
+    declare_builtin_function,
+    elf::ElfError,
+    memory_region::{AccessType, MemoryMapping},
+    program::BuiltinProgram,
+    vm::Config,
+};
+use std::{
+    fmt,
+    mem::{align_of, size_of},
+    slice::from_raw_parts_mut,
+    str::{from_utf8, Utf8Error},
+    sync::Arc,
+};
+
+use crate::{
+    sysvar::Sysvar, transaction_context::Pubkey, ComputeBudget, FeatureSet, InstructionError,
+    InvokeContext, BLAKE3_SYSCALL_ENABLED, ENABLE_PARTITIONED_EPOCH_REWARD,
+    GET_SYSVAR_SYSCALL_ENABLED, LAST_RESTART_SLOT_SYSVAR, MAX_RETURN_DATA,
+    PARTITIONED_EPOCH_REWARDS_SUPERFEATURE, SUCCESS,
+};
+
+pub use self::{hashing::*, logging::*, secp256k1_recover::*, sysvar::*};
+
+mod hashing;
+mod logging;
+mod secp256k1_recover;
+mod sysvar;
+
+/// Error definitions
+#[derive(Debug, PartialEq, Eq)]
+pub enum SyscallError {
+    InvalidString(Utf8Error, Vec<u8>),
+    InvokeContextBorrowFailed,
+    UnalignedPointer,
+    CopyOverlapping,
+    ReturnDataTooLarge(u64, u64),
+    InvalidLength,
+    TooManySlices,
+}
+
+impl std::error::Error for SyscallError {}
+
+impl fmt::Display for SyscallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyscallError::InvalidString(err, bytes) => write!(f, "{err}: {bytes:?}"),
+            SyscallError::InvokeContextBorrowFailed => f.write_str("Cannot borrow invoke context"),
+            SyscallError::UnalignedPointer => f.write_str("Unaligned pointer"),
+            SyscallError::CopyOverlapping => f.write_str("Overlapping copy"),
+            SyscallError::ReturnDataTooLarge(len, max) => {
+                write!(f, "Return data too large ({len} > {max})")
+            }
+            SyscallError::InvalidLength => f.write_str("InvalidLength"),
+            SyscallError::TooManySlices => f.write_str("Hashing too many sequences"),
+        }
+    }
+}
+
+type Error = Box<dyn std::error::Error>;
+
+/// Guest layout of a `&[u8]`, as passed to the syscalls taking an array of slices
+///
+/// Programs are 64 bit, so this is not the host's fat pointer layout on a 32 bit target.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct VmSlice {
+    addr: u64,
+    len: u64,
+}
+
+macro_rules! register_feature_gated_function {
+    ($result:expr, $is_feature_active:expr, $name:expr, $call:expr $(,)?) => {
+        if $is_feature_active {
+            $result.register_function($name, $call)
+        } else {
+            Ok(())
+        }
+    };
+}
+
+/// Builds the SBPF loader environment and registers the syscalls enabled by `feature_set`
+pub fn create_program_runtime_environment_v1<'a>(
+    feature_set: &FeatureSet,
+    compute_budget: &ComputeBudget,
+    debugging_features: bool,
+) -> Result<BuiltinProgram<InvokeContext<'a>>, ElfError> {
+    let epoch_rewards_syscall_enabled = feature_set.is_active(&ENABLE_PARTITIONED_EPOCH_REWARD)
+        || feature_set.is_active(&PARTITIONED_EPOCH_REWARDS_SUPERFEATURE);
+    let last_restart_slot_syscall_enabled = feature_set.is_active(&LAST_RESTART_SLOT_SYSVAR);
+    let get_sysvar_syscall_enabled = feature_set.is_active(&GET_SYSVAR_SYSCALL_ENABLED);
+    let blake3_syscall_enabled = feature_set.is_active(&BLAKE3_SYSCALL_ENABLED);
+
+    let config = Config {
+        max_call_depth: compute_budget.max_call_depth,
+        stack_frame_size: compute_budget.stack_frame_size,
+        enable_address_translation: true,
+        enable_stack_frame_gaps: true,
+        instruction_meter_checkpoint_distance: 10000,
+        enable_instruction_meter: true,
+        enable_instruction_tracing: debugging_features,
+        enable_symbol_and_section_labels: debugging_features,
+        reject_broken_elfs: true,
+        noop_instruction_rate: 256,
+        sanitize_user_provided_values: true,
+        optimize_rodata: false,
+        aligned_memory_mapping: true,
+        ..Config::default()
+    };
+    let mut result = BuiltinProgram::new_loader(config);
+
+    // Hashing
+    result.register_function("sol_sha256", SyscallHash::vm::<Sha256Hasher>)?;
+    result.register_function("sol_keccak256", SyscallHash::vm::<Keccak256Hasher>)?;
+    register_feature_gated_function!(
+        result,
+        blake3_syscall_enabled,
+        "sol_blake3",
+        SyscallHash::vm::<Blake3Hasher>,
+    )?;
+
+    // Secp256k1 Recover
+    result.register_function(
+        "sol_secp256k1_recover",
+        SyscallSecp256k1Recover::vm::<DefaultSecp256k1Backend>,
+    )?;
+
+    // Sysvars
+    result.register_function("sol_get_clock_sysvar", SyscallGetClockSysvar::vm)?;
+    result.register_function(
+        "sol_get_epoch_schedule_sysvar",
+        SyscallGetEpochScheduleSysvar::vm,
+    )?;
+    register_feature_gated_function!(
+        result,
+        epoch_rewards_syscall_enabled,
+        "sol_get_epoch_rewards_sysvar",
+        SyscallGetEpochRewardsSysvar::vm,
+    )?;
+    result.register_function("sol_get_rent_sysvar", SyscallGetRentSysvar::vm)?;
+    register_feature_gated_function!(
+        result,
+        last_restart_slot_syscall_enabled,
+        "sol_get_last_restart_slot",
+        SyscallGetLastRestartSlotSysvar::vm,
+    )?;
+    register_feature_gated_function!(
+        result,
+        get_sysvar_syscall_enabled,
+        "sol_get_sysvar",
+        SyscallGetSysvar::vm,
+    )?;
+
+    // Logging
+    result.register_function("sol_log_", SyscallLog::vm)?;
+    result.register_function("sol_log_64_", SyscallLogU64::vm)?;
+    result.register_function("sol_log_compute_units_", SyscallLogBpfComputeUnits::vm)?;
+    result.register_function("sol_log_pubkey", SyscallLogPubkey::vm)?;
+    result.register_function("sol_log_data", SyscallLogData::vm)?;
+
+    // Return data
+    result.register_function("sol_set_return_data", SyscallSetReturnData::vm)?;
+    result.register_function("sol_get_return_data", SyscallGetReturnData::vm)?;
+
+    Ok(result)
+}
+
+fn consume_compute_meter(invoke_context: &InvokeContext, amount: u64) -> Result<(), Error> {
+    invoke_context.consume_checked(amount)?;
+    Ok(())
+}
+
+fn address_is_aligned<T>(address: u64) -> bool {
+    (address as *mut T as usize)
+        .checked_rem(align_of::<T>())
+        .map(|rem| rem == 0)
+        .expect("T to be non-zero aligned")
+}
+
+fn translate(
+    memory_mapping: &MemoryMapping,
+    access_type: AccessType,
+    vm_addr: u64,
+    len: u64,
+) -> Result<u64, Error> {
+    Result::from(memory_mapping.map(access_type, vm_addr, len)).map_err(|err| err.into())
+}
+
+fn translate_type_inner<'a, T>(
+    memory_mapping: &MemoryMapping,
+    access_type: AccessType,
+    vm_addr: u64,
+    check_aligned: bool,
+) -> Result<&'a mut T, Error> {
+    let host_addr = translate(memory_mapping, access_type, vm_addr, size_of::<T>() as u64)?;
+    if !check_aligned {
+        Ok(unsafe { std::mem::transmute::<u64, &mut T>(host_addr) })
+    } else if !address_is_aligned::<T>(host_addr) {
+        Err(SyscallError::UnalignedPointer.into())
+    } else {
+        Ok(unsafe { &mut *(host_addr as *mut T) })
+    }
+}
+
+fn translate_type_mut<'a, T>(
+    memory_mapping: &MemoryMapping,
+    vm_addr: u64,
+    check_aligned: bool,
+) -> Result<&'a mut T, Error> {
+    translate_type_inner::<T>(memory_mapping, AccessType::Store, vm_addr, check_aligned)
+}
+
+fn translate_type<'a, T>(
+    memory_mapping: &MemoryMapping,
+    vm_addr: u64,
+    check_aligned: bool,
+) -> Result<&'a T, Error> {
+    translate_type_inner::<T>(memory_mapping, AccessType::Load, vm_addr, check_aligned)
+        .map(|value| &*value)
+}
+
+fn translate_slice_inner<'a, T>(
+    memory_mapping: &MemoryMapping,
+    access_type: AccessType,
+    vm_addr: u64,
+    len: u64,
+    check_aligned: bool,
+) -> Result<&'a mut [T], Error> {
+    if len == 0 {
+        return Ok(&mut []);
+    }
+
+    let total_size = len.saturating_mul(size_of::<T>() as u64);
+    if isize::try_from(total_size).is_err() {
+        return Err(SyscallError::InvalidLength.into());
+    }
+
+    let host_addr = translate(memory_mapping, access_type, vm_addr, total_size)?;
+
+    if check_aligned && !address_is_aligned::<T>(host_addr) {
+        return Err(SyscallError::UnalignedPointer.into());
+    }
+    Ok(unsafe { from_raw_parts_mut(host_addr as *mut T, len as usize) })
+}
+
+fn translate_slice_mut<'a, T>(
+    memory_mapping: &MemoryMapping,
+    vm_addr: u64,
+    len: u64,
+    check_aligned: bool,
+) -> Result<&'a mut [T], Error> {
+    translate_slice_inner::<T>(
+        memory_mapping,
+        AccessType::Store,
+        vm_addr,
+        len,
+        check_aligned,
+    )
+}
+
+fn translate_slice<'a, T>(
+    memory_mapping: &MemoryMapping,
+    vm_addr: u64,
+    len: u64,
+    check_aligned: bool,
+) -> Result<&'a [T], Error> {
+    translate_slice_inner::<T>(
+        memory_mapping,
+        AccessType::Load,
+        vm_addr,
+        len,
+        check_aligned,
+    )
+    .map(|value| &*value)
+}
+
+fn translate_string_and_do(
+    memory_mapping: &MemoryMapping,
+    addr: u64,
+    len: u64,
+    check_aligned: bool,
+    work: &mut dyn FnMut(&str) -> Result<u64, Error>,
+) -> Result<u64, Error> {
+    let buf = translate_slice::<u8>(memory_mapping, addr, len, check_aligned)?;
+    match from_utf8(buf) {
+        Ok(message) => work(message),
+        Err(err) => Err(SyscallError::InvalidString(err, buf.to_vec()).into()),
+    }
+}
+
+/// Check that two regions do not overlap.
+fn is_nonoverlapping(src: usize, src_len: usize, dst: usize, dst_len: usize) -> bool {
+    // If the absolute distance between the ptrs is at least as big as the size of the other,
+    // they do not overlap.
+    if src > dst {
+        src.saturating_sub(dst) >= dst_len
+    } else {
+        dst.saturating_sub(src) >= src_len
+    }
+}
+
+declare_builtin_function!(
+    /// Set return data
+    SyscallSetReturnData,
+    fn rust(
+        invoke_context: &mut InvokeContext,
+        addr: u64,
+        len: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Error> {
+        let budget = invoke_context.get_compute_budget();
+
+        let cost = len
+            .checked_div(budget.cpi_bytes_per_unit)
+            .unwrap_or(u64::MAX)
+            .saturating_add(budget.syscall_base_cost);
+        consume_compute_meter(invoke_context, cost)?;
+
+        if len > MAX_RETURN_DATA as u64 {
+            return Err(SyscallError::ReturnDataTooLarge(len, MAX_RETURN_DATA as u64).into());
+        }
+
+        let return_data = if len == 0 {
+            Vec::new()
+        } else {
+            translate_slice::<u8>(
+                memory_mapping,
+                addr,
+                len,
+                invoke_context.get_check_aligned(),
+            )?
+            .to_vec()
+        };
+        let transaction_context = &mut invoke_context.transaction_context;
+        let program_id = *transaction_context
+            .get_current_instruction_context()
+            .and_then(|instruction_context| {
+                instruction_context.get_last_program_key(transaction_context)
+            })?;
+
+        transaction_context.set_return_data(program_id, return_data)?;
+
+        Ok(0)
+    }
+);
+
+declare_builtin_function!(
+    /// Get return data
+    SyscallGetReturnData,
+    fn rust(
+        invoke_context: &mut InvokeContext,
+        return_data_addr: u64,
+        length: u64,
+        program_id_addr: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Error> {
+        let budget = invoke_context.get_compute_budget();
+
+        consume_compute_meter(invoke_context, budget.syscall_base_cost)?;
+
+        let (program_id, return_data) = invoke_context.transaction_context.get_return_data();
+        let length = length.min(return_data.len() as u64);
+        if length != 0 {
+            let cost = length
+                .saturating_add(size_of::<Pubkey>() as u64)
+                .checked_div(budget.cpi_bytes_per_unit)
+                .unwrap_or(u64::MAX);
+            consume_compute_meter(invoke_context, cost)?;
+
+            let return_data_result = translate_slice_mut::<u8>(
+                memory_mapping,
+                return_data_addr,
+                length,
+                invoke_context.get_check_aligned(),
+            )?;
+
+            let to_slice = return_data_result;
+            let from_slice = return_data
+                .get(..length as usize)
+                .ok_or(SyscallError::InvokeContextBorrowFailed)?;
+            if to_slice.len() != from_slice.len() {
+                return Err(SyscallError::InvalidLength.into());
+            }
+            to_slice.copy_from_slice(from_slice);
+
+            let program_id_result = translate_type_mut::<Pubkey>(
+                memory_mapping,
+                program_id_addr,
+                invoke_context.get_check_aligned(),
+            )?;
+
+            if !is_nonoverlapping(
+                to_slice.as_ptr() as usize,
+                length as usize,
+                program_id_result as *const _ as usize,
+                size_of::<Pubkey>(),
+            ) {
+                return Err(SyscallError::CopyOverlapping.into());
+            }
+
+            *program_id_result = *program_id;
+        }
+
+        // Return the actual length, rather the length returned
+        Ok(return_data.len() as u64)
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_mock_invoke_context, AccountSharedData};
+    use solana_sbpf::{
+        ebpf::{MM_HEAP_START, MM_RODATA_START, MM_STACK_START},
+        memory_region::MemoryRegion,
+        program::SBPFVersion,
+    };
+
+    const PROGRAM_ID: Pubkey = [1; 32];
+
+    fn push_program(invoke_context: &mut InvokeContext) {
+        invoke_context
+            .transaction_context
+            .get_next_instruction_context()
+            .unwrap()
+            .configure(&[0], &[], &[]);
+        invoke_context.push().unwrap();
+    }
+
+    #[test]
+    fn test_syscall_set_return_data() {
+        let transaction_accounts = vec![(PROGRAM_ID, AccountSharedData::default())];
+        with_mock_invoke_context!(invoke_context, transaction_context, transaction_accounts);
+        push_program(&mut invoke_context);
+
+        let data = vec![7u8; MAX_RETURN_DATA + 1];
+        let config = Config::default();
+        let mut memory_mapping = MemoryMapping::new(
+            vec![MemoryRegion::new_readonly(&data, MM_RODATA_START)],
+            &config,
+            SBPFVersion::V0,
+        )
+        .unwrap();
+
+        let err = SyscallSetReturnData::rust(
+            &mut invoke_context,
+            MM_RODATA_START,
+            MAX_RETURN_DATA as u64 + 1,
+            0,
+            0,
+            0,
+            &mut memory_mapping,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<SyscallError>(),
+            Some(&SyscallError::ReturnDataTooLarge(
+                MAX_RETURN_DATA as u64 + 1,
+                MAX_RETURN_DATA as u64
+            ))
+        );
+        assert_eq!(
+            invoke_context.transaction_context.get_return_data(),
+            (&Pubkey::default(), &[][..])
+        );
+
+        let result = SyscallSetReturnData::rust(
+            &mut invoke_context,
+            MM_RODATA_START,
+            MAX_RETURN_DATA as u64,
+            0,
+            0,
+            0,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(
+            invoke_context.transaction_context.get_return_data(),
+            (&PROGRAM_ID, &data[..MAX_RETURN_DATA])
+        );
+    }
+
+    #[test]
+    fn test_syscall_get_return_data() {
+        with_mock_invoke_context!(invoke_context, transaction_context, vec![]);
+        invoke_context
+            .transaction_context
+            .set_return_data(PROGRAM_ID, vec![1, 2, 3, 4])
+            .unwrap();
+
+        let mut short_buffer = [0u8; 2];
+        let mut long_buffer = [0u8; 6];
+        let mut program_id = Pubkey::default();
+        let config = Config::default();
+        let mut memory_mapping = MemoryMapping::new(
+            vec![
+                MemoryRegion::new_writable(&mut short_buffer, MM_RODATA_START),
+                MemoryRegion::new_writable(&mut long_buffer, MM_STACK_START),
+                MemoryRegion::new_writable(&mut program_id, MM_HEAP_START),
+            ],
+            &config,
+            SBPFVersion::V0,
+        )
+        .unwrap();
+
+        // The full length is returned even when the buffer is shorter
+        let result = SyscallGetReturnData::rust(
+            &mut invoke_context,
+            MM_RODATA_START,
+            short_buffer.len() as u64,
+            MM_HEAP_START,
+            0,
+            0,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), 4);
+        assert_eq!(short_buffer, [1, 2]);
+        assert_eq!(program_id, PROGRAM_ID);
+
+        // A longer buffer only receives the available bytes
+        let result = SyscallGetReturnData::rust(
+            &mut invoke_context,
+            MM_STACK_START,
+            long_buffer.len() as u64,
+            MM_HEAP_START,
+            0,
+            0,
+            &mut memory_mapping,
+        );
+        assert_eq!(result.unwrap(), 4);
+        assert_eq!(long_buffer, [1, 2, 3, 4, 0, 0]);
+    }
+}
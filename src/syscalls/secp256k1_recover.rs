@@ -0,0 +1,298 @@
+use super::*;
+use crate::HASH_BYTES;
+
+pub const SECP256K1_SIGNATURE_LENGTH: usize = 64;
+pub const SECP256K1_PUBLIC_KEY_LENGTH: usize = 64;
+
+/// Failure modes of [SyscallSecp256k1Recover], returned to the program as status codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Secp256k1RecoverError {
+    InvalidHash,
+    InvalidRecoveryId,
+    InvalidSignature,
+}
+
+impl From<Secp256k1RecoverError> for u64 {
+    fn from(error: Secp256k1RecoverError) -> Self {
+        match error {
+            Secp256k1RecoverError::InvalidHash => 1,
+            Secp256k1RecoverError::InvalidRecoveryId => 2,
+            Secp256k1RecoverError::InvalidSignature => 3,
+        }
+    }
+}
+
+/// Public key recovery used by [SyscallSecp256k1Recover]
+///
+/// Implementations must agree with [LibSecp256k1Backend] on every input, since the result
+/// is visible to programs.
+pub trait Secp256k1RecoverBackend {
+    /// Recovers the uncompressed public key, without its `0x04` prefix
+    fn recover(
+        hash: &[u8; HASH_BYTES],
+        recovery_id: u8,
+        signature: &[u8; SECP256K1_SIGNATURE_LENGTH],
+    ) -> Result<[u8; SECP256K1_PUBLIC_KEY_LENGTH], Secp256k1RecoverError>;
+}
+
+/// Software backend using the same library as Agave
+pub struct LibSecp256k1Backend;
+
+impl Secp256k1RecoverBackend for LibSecp256k1Backend {
+    fn recover(
+        hash: &[u8; HASH_BYTES],
+        recovery_id: u8,
+        signature: &[u8; SECP256K1_SIGNATURE_LENGTH],
+    ) -> Result<[u8; SECP256K1_PUBLIC_KEY_LENGTH], Secp256k1RecoverError> {
+        let message = libsecp256k1::Message::parse_slice(hash)
+            .map_err(|_| Secp256k1RecoverError::InvalidHash)?;
+        let recovery_id = libsecp256k1::RecoveryId::parse(recovery_id)
+            .map_err(|_| Secp256k1RecoverError::InvalidRecoveryId)?;
+        let signature = libsecp256k1::Signature::parse_standard_slice(signature)
+            .map_err(|_| Secp256k1RecoverError::InvalidSignature)?;
+
+        let public_key = libsecp256k1::recover(&message, &signature, &recovery_id)
+            .map_err(|_| Secp256k1RecoverError::InvalidSignature)?
+            .serialize();
+        let mut result = [0u8; SECP256K1_PUBLIC_KEY_LENGTH];
+        result.copy_from_slice(&public_key[1..65]);
+        Ok(result)
+    }
+}
+
+/// Backend built on `k256`, which SP1 guests patch to use the secp256k1 precompiles
+#[cfg(feature = "sp1")]
+pub struct Sp1Secp256k1Backend;
+
+#[cfg(feature = "sp1")]
+impl Secp256k1RecoverBackend for Sp1Secp256k1Backend {
+    fn recover(
+        hash: &[u8; HASH_BYTES],
+        recovery_id: u8,
+        signature: &[u8; SECP256K1_SIGNATURE_LENGTH],
+    ) -> Result<[u8; SECP256K1_PUBLIC_KEY_LENGTH], Secp256k1RecoverError> {
+        use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+        let recovery_id =
+            RecoveryId::from_byte(recovery_id).ok_or(Secp256k1RecoverError::InvalidRecoveryId)?;
+        let signature = Signature::from_slice(signature)
+            .map_err(|_| Secp256k1RecoverError::InvalidSignature)?;
+        // k256 only verifies low-S signatures while libsecp256k1 accepts both. Negating S
+        // flips the parity of the recovered point, so the same key comes out either way.
+        let (signature, recovery_id) = match signature.normalize_s() {
+            Some(normalized) => (
+                normalized,
+                RecoveryId::new(!recovery_id.is_y_odd(), recovery_id.is_x_reduced()),
+            ),
+            None => (signature, recovery_id),
+        };
+
+        let public_key = VerifyingKey::recover_from_prehash(hash, &signature, recovery_id)
+            .map_err(|_| Secp256k1RecoverError::InvalidSignature)?
+            .to_encoded_point(false);
+        let mut result = [0u8; SECP256K1_PUBLIC_KEY_LENGTH];
+        result.copy_from_slice(&public_key.as_bytes()[1..65]);
+        Ok(result)
+    }
+}
+
+/// Backend registered by [create_program_runtime_environment_v1]
+#[cfg(not(all(feature = "sp1", target_os = "zkvm")))]
+pub type DefaultSecp256k1Backend = LibSecp256k1Backend;
+/// Backend registered by [create_program_runtime_environment_v1]
+#[cfg(all(feature = "sp1", target_os = "zkvm"))]
+pub type DefaultSecp256k1Backend = Sp1Secp256k1Backend;
+
+declare_builtin_function!(
+    /// secp256k1_recover
+    SyscallSecp256k1Recover<B: Secp256k1RecoverBackend>,
+    fn rust(
+        invoke_context: &mut InvokeContext,
+        hash_addr: u64,
+        recovery_id_val: u64,
+        signature_addr: u64,
+        result_addr: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Error> {
+        let cost = invoke_context.get_compute_budget().secp256k1_recover_cost;
+        consume_compute_meter(invoke_context, cost)?;
+
+        let hash = translate_type::<[u8; HASH_BYTES]>(
+            memory_mapping,
+            hash_addr,
+            invoke_context.get_check_aligned(),
+        )?;
+        let signature = translate_type::<[u8; SECP256K1_SIGNATURE_LENGTH]>(
+            memory_mapping,
+            signature_addr,
+            invoke_context.get_check_aligned(),
+        )?;
+        let secp256k1_recover_result = translate_slice_mut::<u8>(
+            memory_mapping,
+            result_addr,
+            SECP256K1_PUBLIC_KEY_LENGTH as u64,
+            invoke_context.get_check_aligned(),
+        )?;
+
+        let Ok(recovery_id) = recovery_id_val.try_into() else {
+            return Ok(Secp256k1RecoverError::InvalidRecoveryId.into());
+        };
+        let public_key = match B::recover(hash, recovery_id, signature) {
+            Ok(public_key) => public_key,
+            Err(error) => return Ok(error.into()),
+        };
+
+        secp256k1_recover_result.copy_from_slice(&public_key);
+        Ok(SUCCESS)
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::with_mock_invoke_context;
+    use solana_sbpf::{
+        ebpf::{MM_HEAP_START, MM_RODATA_START, MM_STACK_START},
+        memory_region::MemoryRegion,
+        program::SBPFVersion,
+    };
+
+    const HASH: [u8; HASH_BYTES] = [7; HASH_BYTES];
+
+    /// Public key of the secret key `1`, which is the generator point
+    const GENERATOR: [u8; SECP256K1_PUBLIC_KEY_LENGTH] = [
+        0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87, 0x0b,
+        0x07, 0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b, 0x16, 0xf8,
+        0x17, 0x98, 0x48, 0x3a, 0xda, 0x77, 0x26, 0xa3, 0xc4, 0x65, 0x5d, 0xa4, 0xfb, 0xfc, 0x0e,
+        0x11, 0x08, 0xa8, 0xfd, 0x17, 0xb4, 0x48, 0xa6, 0x85, 0x54, 0x19, 0x9c, 0x47, 0xd0, 0x8f,
+        0xfb, 0x10, 0xd4, 0xb8,
+    ];
+
+    /// Order of the curve, the smallest S that does not parse
+    const CURVE_ORDER: [u8; 32] = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xfe, 0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36,
+        0x41, 0x41,
+    ];
+
+    /// Low-S signature of [HASH] by the secret key `1` and its recovery id
+    fn sign() -> ([u8; SECP256K1_SIGNATURE_LENGTH], u8) {
+        let mut secret_key = [0; 32];
+        secret_key[31] = 1;
+        let (signature, recovery_id) = libsecp256k1::sign(
+            &libsecp256k1::Message::parse(&HASH),
+            &libsecp256k1::SecretKey::parse(&secret_key).unwrap(),
+        );
+        (signature.serialize(), recovery_id.serialize())
+    }
+
+    /// The same signature with S negated, which recovers the same key with the other parity
+    fn sign_high_s() -> ([u8; SECP256K1_SIGNATURE_LENGTH], u8) {
+        let (signature, recovery_id) = sign();
+        let signature = libsecp256k1::Signature::parse_standard(&signature).unwrap();
+        let signature = libsecp256k1::Signature {
+            r: signature.r,
+            s: -signature.s,
+        };
+        (signature.serialize(), recovery_id ^ 1)
+    }
+
+    fn syscall_secp256k1_recover(
+        hash: &[u8; HASH_BYTES],
+        recovery_id: u64,
+        signature: &[u8; SECP256K1_SIGNATURE_LENGTH],
+    ) -> (u64, [u8; SECP256K1_PUBLIC_KEY_LENGTH]) {
+        with_mock_invoke_context!(invoke_context, transaction_context, Vec::new());
+        let mut result = [0; SECP256K1_PUBLIC_KEY_LENGTH];
+        let config = Config::default();
+        let mut memory_mapping = MemoryMapping::new(
+            vec![
+                MemoryRegion::new_readonly(hash, MM_RODATA_START),
+                MemoryRegion::new_readonly(signature, MM_STACK_START),
+                MemoryRegion::new_writable(&mut result, MM_HEAP_START),
+            ],
+            &config,
+            SBPFVersion::V0,
+        )
+        .unwrap();
+        let status = SyscallSecp256k1Recover::rust::<LibSecp256k1Backend>(
+            &mut invoke_context,
+            MM_RODATA_START,
+            recovery_id,
+            MM_STACK_START,
+            MM_HEAP_START,
+            0,
+            &mut memory_mapping,
+        )
+        .unwrap();
+        (status, result)
+    }
+
+    #[test]
+    fn test_syscall_secp256k1_recover() {
+        let (signature, recovery_id) = sign();
+        assert_eq!(
+            syscall_secp256k1_recover(&HASH, recovery_id.into(), &signature),
+            (SUCCESS, GENERATOR)
+        );
+
+        // High-S signatures are accepted, like in Agave
+        let (signature, recovery_id) = sign_high_s();
+        assert_eq!(
+            syscall_secp256k1_recover(&HASH, recovery_id.into(), &signature),
+            (SUCCESS, GENERATOR)
+        );
+    }
+
+    #[test]
+    fn test_syscall_secp256k1_recover_errors() {
+        let (signature, recovery_id) = sign();
+        let invalid_recovery_id = u64::from(Secp256k1RecoverError::InvalidRecoveryId);
+        let invalid_signature = u64::from(Secp256k1RecoverError::InvalidSignature);
+        assert_eq!(u64::from(Secp256k1RecoverError::InvalidHash), 1);
+        assert_eq!(invalid_recovery_id, 2);
+        assert_eq!(invalid_signature, 3);
+
+        for recovery_id in [4, 255, 256, u64::MAX] {
+            assert_eq!(
+                syscall_secp256k1_recover(&HASH, recovery_id, &signature).0,
+                invalid_recovery_id
+            );
+        }
+
+        let mut zero_r = signature;
+        zero_r[..32].fill(0);
+        let mut zero_s = signature;
+        zero_s[32..].fill(0);
+        let mut overflowing_s = signature;
+        overflowing_s[32..].copy_from_slice(&CURVE_ORDER);
+        for signature in [zero_r, zero_s, overflowing_s] {
+            assert_eq!(
+                syscall_secp256k1_recover(&HASH, recovery_id.into(), &signature),
+                (invalid_signature, [0; SECP256K1_PUBLIC_KEY_LENGTH])
+            );
+        }
+    }
+
+    #[cfg(feature = "sp1")]
+    #[test]
+    fn test_sp1_backend_matches_libsecp256k1() {
+        let (signature, _) = sign();
+        let (high_s_signature, _) = sign_high_s();
+        let mut zero_r = signature;
+        zero_r[..32].fill(0);
+        let mut overflowing_s = signature;
+        overflowing_s[32..].copy_from_slice(&CURVE_ORDER);
+
+        for signature in [signature, high_s_signature, zero_r, overflowing_s] {
+            for recovery_id in 0..=4 {
+                assert_eq!(
+                    Sp1Secp256k1Backend::recover(&HASH, recovery_id, &signature),
+                    LibSecp256k1Backend::recover(&HASH, recovery_id, &signature),
+                    "recovery id {recovery_id}, signature {signature:?}"
+                );
+            }
+        }
+    }
+}
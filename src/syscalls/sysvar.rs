@@ -0,0 +1,300 @@
+use super::*;
+
+fn get_sysvar<T: Sysvar + Clone>(
+    sysvar: Result<Arc<T>, InstructionError>,
+    var_addr: u64,
+    check_aligned: bool,
+    memory_mapping: &mut MemoryMapping,
+    invoke_context: &mut InvokeContext,
+) -> Result<u64, Error> {
+    consume_compute_meter(
+        invoke_context,
+        invoke_context
+            .get_compute_budget()
+            .sysvar_base_cost
+            .saturating_add(size_of::<T>() as u64),
+    )?;
+    let var = translate_type_mut::<T>(memory_mapping, var_addr, check_aligned)?;
+
+    // this clone looks unecessary now, but it exists to zero out trailing alignment bytes
+    let sysvar: Arc<T> = sysvar?;
+    *var = T::clone(sysvar.as_ref());
+
+    Ok(SUCCESS)
+}
+
+declare_builtin_function!(
+    /// Get a Clock sysvar
+    SyscallGetClockSysvar,
+    fn rust(
+        invoke_context: &mut InvokeContext,
+        var_addr: u64,
+        _arg2: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Error> {
+        get_sysvar(
+            invoke_context.get_sysvar_cache().get_clock(),
+            var_addr,
+            invoke_context.get_check_aligned(),
+            memory_mapping,
+            invoke_context,
+        )
+    }
+);
+
+declare_builtin_function!(
+    /// Get a EpochSchedule sysvar
+    SyscallGetEpochScheduleSysvar,
+    fn rust(
+        invoke_context: &mut InvokeContext,
+        var_addr: u64,
+        _arg2: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Error> {
+        get_sysvar(
+            invoke_context.get_sysvar_cache().get_epoch_schedule(),
+            var_addr,
+            invoke_context.get_check_aligned(),
+            memory_mapping,
+            invoke_context,
+        )
+    }
+);
+
+declare_builtin_function!(
+    /// Get a EpochRewards sysvar
+    SyscallGetEpochRewardsSysvar,
+    fn rust(
+        invoke_context: &mut InvokeContext,
+        var_addr: u64,
+        _arg2: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Error> {
+        get_sysvar(
+            invoke_context.get_sysvar_cache().get_epoch_rewards(),
+            var_addr,
+            invoke_context.get_check_aligned(),
+            memory_mapping,
+            invoke_context,
+        )
+    }
+);
+
+declare_builtin_function!(
+    /// Get a Rent sysvar
+    SyscallGetRentSysvar,
+    fn rust(
+        invoke_context: &mut InvokeContext,
+        var_addr: u64,
+        _arg2: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Error> {
+        get_sysvar(
+            invoke_context.get_sysvar_cache().get_rent(),
+            var_addr,
+            invoke_context.get_check_aligned(),
+            memory_mapping,
+            invoke_context,
+        )
+    }
+);
+
+declare_builtin_function!(
+    /// Get a Last Restart Slot sysvar
+    SyscallGetLastRestartSlotSysvar,
+    fn rust(
+        invoke_context: &mut InvokeContext,
+        var_addr: u64,
+        _arg2: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Error> {
+        get_sysvar(
+            invoke_context.get_sysvar_cache().get_last_restart_slot(),
+            var_addr,
+            invoke_context.get_check_aligned(),
+            memory_mapping,
+            invoke_context,
+        )
+    }
+);
+
+const SYSVAR_NOT_FOUND: u64 = 2;
+const OFFSET_LENGTH_EXCEEDS_SYSVAR: u64 = 1;
+
+declare_builtin_function!(
+    /// Get a slice of a Sysvar in-memory representation
+    SyscallGetSysvar,
+    fn rust(
+        invoke_context: &mut InvokeContext,
+        sysvar_id_addr: u64,
+        var_addr: u64,
+        offset: u64,
+        length: u64,
+        _arg5: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Error> {
+        let check_aligned = invoke_context.get_check_aligned();
+        let ComputeBudget {
+            sysvar_base_cost,
+            cpi_bytes_per_unit,
+            mem_op_base_cost,
+            ..
+        } = *invoke_context.get_compute_budget();
+
+        // Abort: "Compute budget is exceeded."
+        let sysvar_id_cost = 32_u64.checked_div(cpi_bytes_per_unit).unwrap_or(0);
+        let sysvar_buf_cost = length.checked_div(cpi_bytes_per_unit).unwrap_or(0);
+        consume_compute_meter(
+            invoke_context,
+            sysvar_base_cost
+                .saturating_add(sysvar_id_cost)
+                .saturating_add(std::cmp::max(sysvar_buf_cost, mem_op_base_cost)),
+        )?;
+
+        // Abort: "Not all bytes in VM memory range `[sysvar_id, sysvar_id + 32)` are readable."
+        let sysvar_id = translate_type::<Pubkey>(memory_mapping, sysvar_id_addr, check_aligned)?;
+
+        // Abort: "Not all bytes in VM memory range `[var_addr, var_addr + length)` are writable."
+        let var = translate_slice_mut::<u8>(memory_mapping, var_addr, length, check_aligned)?;
+
+        // Abort: "`offset + length` is not in `[0, 2^64)`."
+        let offset_length = offset
+            .checked_add(length)
+            .ok_or(InstructionError::ArithmeticOverflow)?;
+
+        // Abort: "`var_addr + length` is not in `[0, 2^64)`."
+        let _ = var_addr
+            .checked_add(length)
+            .ok_or(InstructionError::ArithmeticOverflow)?;
+
+        let cache = invoke_context.get_sysvar_cache();
+
+        // "`2` if the sysvar data is not present in the Sysvar Cache."
+        let sysvar_buf = match cache.sysvar_id_to_buffer(sysvar_id) {
+            None => return Ok(SYSVAR_NOT_FOUND),
+            Some(ref sysvar_buf) => sysvar_buf,
+        };
+
+        // "`1` if `offset + length` is greater than the length of the sysvar data."
+        // On 32-bit hosts a range that does not fit in `usize` is past the end as well.
+        let (Ok(start), Ok(end)) = (usize::try_from(offset), usize::try_from(offset_length)) else {
+            return Ok(OFFSET_LENGTH_EXCEEDS_SYSVAR);
+        };
+        if let Some(sysvar_slice) = sysvar_buf.get(start..end) {
+            var.copy_from_slice(sysvar_slice);
+        } else {
+            return Ok(OFFSET_LENGTH_EXCEEDS_SYSVAR);
+        }
+
+        Ok(SUCCESS)
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        sysvar::{Clock, Rent, SysvarId},
+        with_mock_invoke_context, EnvironmentConfig, Hash, SysvarCache,
+    };
+    use solana_sbpf::{
+        ebpf::{MM_HEAP_START, MM_RODATA_START, MM_STACK_START},
+        memory_region::MemoryRegion,
+        program::SBPFVersion,
+        vm::Config,
+    };
+
+    #[test]
+    fn test_syscall_get_sysvar() {
+        let clock = Clock {
+            slot: 1,
+            epoch_start_timestamp: 2,
+            epoch: 3,
+            leader_schedule_epoch: 4,
+            unix_timestamp: 5,
+        };
+        let clock_data = bincode::serialize(&clock).unwrap();
+        let mut sysvar_cache = SysvarCache::default();
+        sysvar_cache.set_sysvar_for_tests(&clock);
+        let get_epoch_vote_account_stake = |_: &Pubkey| 0;
+
+        with_mock_invoke_context!(invoke_context, transaction_context, vec![]);
+        invoke_context.environment_config = EnvironmentConfig::new(
+            Hash::default(),
+            0,
+            0,
+            &get_epoch_vote_account_stake,
+            Arc::default(),
+            &sysvar_cache,
+        );
+
+        let clock_id = Clock::id();
+        let rent_id = Rent::id();
+        let mut var = [0u8; 8];
+        let config = Config::default();
+        let mut memory_mapping = MemoryMapping::new(
+            vec![
+                MemoryRegion::new_readonly(&clock_id, MM_RODATA_START),
+                MemoryRegion::new_readonly(&rent_id, MM_STACK_START),
+                MemoryRegion::new_writable(&mut var, MM_HEAP_START),
+            ],
+            &config,
+            SBPFVersion::V0,
+        )
+        .unwrap();
+        let mut get_sysvar = |sysvar_id_addr: u64, offset: u64| {
+            SyscallGetSysvar::rust(
+                &mut invoke_context,
+                sysvar_id_addr,
+                MM_HEAP_START,
+                offset,
+                8,
+                0,
+                &mut memory_mapping,
+            )
+        };
+
+        assert_eq!(get_sysvar(MM_RODATA_START, 8).unwrap(), SUCCESS);
+        assert_eq!(var, clock_data[8..16]);
+
+        // The Rent sysvar is not in the cache
+        assert_eq!(get_sysvar(MM_STACK_START, 0).unwrap(), SYSVAR_NOT_FOUND);
+
+        // The last 8 bytes can be read, one more is past the end
+        let last = clock_data.len() as u64 - 8;
+        assert_eq!(get_sysvar(MM_RODATA_START, last).unwrap(), SUCCESS);
+        assert_eq!(var, clock_data[clock_data.len() - 8..]);
+        assert_eq!(
+            get_sysvar(MM_RODATA_START, last + 1).unwrap(),
+            OFFSET_LENGTH_EXCEEDS_SYSVAR
+        );
+
+        // A range past the end of memory is past the end of the sysvar, too
+        assert_eq!(
+            get_sysvar(MM_RODATA_START, u64::MAX - 8).unwrap(),
+            OFFSET_LENGTH_EXCEEDS_SYSVAR
+        );
+
+        // `offset + length` must not overflow
+        let err = get_sysvar(MM_RODATA_START, u64::MAX - 7).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<InstructionError>(),
+            Some(&InstructionError::ArithmeticOverflow)
+        );
+    }
+}
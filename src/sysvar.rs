@@ -0,0 +1,540 @@
+//! Sysvar ids and account layouts
+//!
+//! Kept out of the crate root because [Clock] would clash with the
+//! [crate::Clock] used for timings.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    environment_config::{Fees, Hash, RecentBlockhashes, SlotHashes, StakeHistory},
+    transaction_context::Pubkey,
+    InstructionError,
+};
+
+pub const CLOCK_ID: Pubkey = [
+    6, 167, 213, 23, 24, 199, 116, 201, 40, 86, 99, 152, 105, 29, 94, 182, 139, 94, 184, 163, 155,
+    75, 109, 92, 115, 85, 91, 33, 0, 0, 0, 0,
+];
+pub const EPOCH_SCHEDULE_ID: Pubkey = [
+    6, 167, 213, 23, 24, 220, 63, 238, 2, 211, 228, 127, 1, 0, 248, 176, 84, 247, 148, 46, 96, 89,
+    30, 63, 80, 135, 25, 168, 5, 0, 0, 0,
+];
+pub const EPOCH_REWARDS_ID: Pubkey = [
+    6, 167, 213, 23, 24, 220, 63, 238, 2, 165, 88, 191, 131, 206, 102, 225, 68, 66, 42, 28, 52,
+    149, 11, 39, 193, 134, 155, 90, 156, 0, 0, 0,
+];
+pub const RENT_ID: Pubkey = [
+    6, 167, 213, 23, 25, 44, 92, 81, 33, 140, 201, 76, 61, 74, 241, 127, 88, 218, 238, 8, 155, 161,
+    253, 68, 227, 219, 217, 138, 0, 0, 0, 0,
+];
+pub const SLOT_HASHES_ID: Pubkey = [
+    6, 167, 213, 23, 25, 47, 10, 175, 198, 242, 101, 227, 251, 119, 204, 122, 218, 130, 197, 41,
+    208, 190, 59, 19, 110, 45, 0, 85, 32, 0, 0, 0,
+];
+pub const STAKE_HISTORY_ID: Pubkey = [
+    6, 167, 213, 23, 25, 53, 132, 208, 254, 237, 155, 179, 67, 29, 19, 32, 107, 229, 68, 40, 27,
+    87, 184, 86, 108, 197, 55, 95, 244, 0, 0, 0,
+];
+pub const LAST_RESTART_SLOT_ID: Pubkey = [
+    6, 167, 213, 23, 25, 6, 221, 225, 205, 63, 148, 125, 202, 180, 200, 244, 244, 245, 27, 173, 15,
+    152, 19, 184, 0, 210, 137, 71, 31, 192, 0, 0,
+];
+pub const FEES_ID: Pubkey = [
+    6, 167, 213, 23, 24, 226, 90, 141, 131, 80, 60, 37, 26, 122, 240, 113, 38, 253, 114, 0, 223,
+    111, 196, 237, 82, 106, 156, 144, 0, 0, 0, 0,
+];
+pub const RECENT_BLOCKHASHES_ID: Pubkey = [
+    6, 167, 213, 23, 25, 44, 86, 142, 224, 138, 132, 95, 115, 210, 151, 136, 207, 3, 92, 49, 69,
+    178, 26, 179, 68, 216, 6, 46, 169, 64, 0, 0,
+];
+
+/// A type that is stored in a sysvar account
+pub trait SysvarId {
+    const ID: Pubkey;
+
+    fn id() -> Pubkey {
+        Self::ID
+    }
+
+    fn check_id(pubkey: &Pubkey) -> bool {
+        *pubkey == Self::ID
+    }
+}
+
+macro_rules! impl_sysvar_id {
+    ($type:ty, $id:expr) => {
+        impl SysvarId for $type {
+            const ID: Pubkey = $id;
+        }
+    };
+}
+
+impl_sysvar_id!(Clock, CLOCK_ID);
+impl_sysvar_id!(EpochSchedule, EPOCH_SCHEDULE_ID);
+impl_sysvar_id!(EpochRewards, EPOCH_REWARDS_ID);
+impl_sysvar_id!(Rent, RENT_ID);
+impl_sysvar_id!(SlotHashes, SLOT_HASHES_ID);
+impl_sysvar_id!(StakeHistory, STAKE_HISTORY_ID);
+impl_sysvar_id!(LastRestartSlot, LAST_RESTART_SLOT_ID);
+impl_sysvar_id!(Fees, FEES_ID);
+impl_sysvar_id!(RecentBlockhashes, RECENT_BLOCKHASHES_ID);
+
+/// A sysvar whose account data is the bincode encoding of the type
+pub trait Sysvar: SysvarId + Default + Serialize + DeserializeOwned {
+    /// Size of the account data, all sysvars here have a fixed size
+    fn size_of() -> usize {
+        bincode::serialized_size(&Self::default()).unwrap() as usize
+    }
+
+    /// Decodes account data, ignoring trailing bytes like the runtime does
+    fn from_account_data(data: &[u8]) -> Result<Self, InstructionError> {
+        bincode::deserialize(data).map_err(|_| InstructionError::InvalidAccountData)
+    }
+
+    /// Encodes the sysvar into account data
+    fn to_account_data(&self) -> Result<Vec<u8>, InstructionError> {
+        bincode::serialize(self).map_err(|_| InstructionError::GenericError)
+    }
+}
+
+impl Sysvar for Clock {}
+impl Sysvar for EpochSchedule {}
+impl Sysvar for EpochRewards {}
+impl Sysvar for Rent {}
+impl Sysvar for LastRestartSlot {}
+
+/// The default tick rate that the cluster attempts to achieve (160 per second).
+pub const DEFAULT_TICKS_PER_SECOND: u64 = 160;
+
+/// The number of ticks in a slot
+pub const DEFAULT_TICKS_PER_SLOT: u64 = 64;
+
+/// The number of seconds in a day
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// The number of ticks in a day
+pub const TICKS_PER_DAY: u64 = DEFAULT_TICKS_PER_SECOND * SECONDS_PER_DAY;
+
+/// The number of slots in an epoch, 2 days on mainnet
+pub const DEFAULT_SLOTS_PER_EPOCH: u64 = 2 * TICKS_PER_DAY / DEFAULT_TICKS_PER_SLOT;
+
+/// A representation of network time.
+///
+/// All members of `Clock` start from 0 upon network boot.
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Clock {
+    /// The current `Slot`.
+    pub slot: u64,
+    /// The timestamp of the first `Slot` in this `Epoch`.
+    pub epoch_start_timestamp: i64,
+    /// The current `Epoch`.
+    pub epoch: u64,
+    /// The future `Epoch` for which the leader schedule has
+    /// most recently been calculated.
+    pub leader_schedule_epoch: u64,
+    /// The approximate real world time of the current slot.
+    ///
+    /// This value was originally computed from genesis creation time and
+    /// network time in slots, incurring a lot of drift. Following activation of
+    /// the [`timestamp_correction` and `timestamp_bounding`][tsc] features it
+    /// is calculated using a [validator timestamp oracle][oracle].
+    ///
+    /// [tsc]: https://docs.solanalabs.com/implemented-proposals/bank-timestamp-correction
+    /// [oracle]: https://docs.solanalabs.com/implemented-proposals/validator-timestamp-oracle
+    pub unix_timestamp: i64,
+}
+
+/// Default rental rate in lamports/byte-year.
+///
+/// This calculation is based on:
+/// - 10^9 lamports per SOL
+/// - $1 per SOL
+/// - $0.01 per megabyte day
+/// - $3.65 per megabyte year
+pub const DEFAULT_LAMPORTS_PER_BYTE_YEAR: u64 = 1_000_000_000 / 100 * 365 / (1024 * 1024);
+
+/// Default amount of time (in years) the balance has to include rent for the
+/// account to be rent exempt.
+pub const DEFAULT_EXEMPTION_THRESHOLD: f64 = 2.0;
+
+/// Default percentage of collected rent that is burned.
+///
+/// Valid values are in the range [0, 100]. The remaining percentage is
+/// distributed to validators.
+pub const DEFAULT_BURN_PERCENT: u8 = 50;
+
+/// Account storage overhead for calculation of base rent.
+///
+/// This is the number of bytes required to store an account with no data. It is
+/// added to an accounts data length when calculating [`Rent::minimum_balance`].
+pub const ACCOUNT_STORAGE_OVERHEAD: u64 = 128;
+
+/// Configuration of network rent.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rent {
+    /// Rental rate in lamports/byte-year.
+    pub lamports_per_byte_year: u64,
+
+    /// Amount of time (in years) a balance must include rent for the account to
+    /// be rent exempt.
+    pub exemption_threshold: f64,
+
+    /// The percentage of collected rent that is burned.
+    ///
+    /// Valid values are in the range [0, 100]. The remaining percentage is
+    /// distributed to validators.
+    pub burn_percent: u8,
+}
+
+impl Default for Rent {
+    fn default() -> Self {
+        Self {
+            lamports_per_byte_year: DEFAULT_LAMPORTS_PER_BYTE_YEAR,
+            exemption_threshold: DEFAULT_EXEMPTION_THRESHOLD,
+            burn_percent: DEFAULT_BURN_PERCENT,
+        }
+    }
+}
+
+impl Rent {
+    /// Calculate how much rent to burn from the collected rent.
+    ///
+    /// The first value returned is the amount burned. The second is the amount
+    /// to distribute to validators.
+    pub fn calculate_burn(&self, rent_collected: u64) -> (u64, u64) {
+        let burned_portion = (rent_collected * u64::from(self.burn_percent)) / 100;
+        (burned_portion, rent_collected - burned_portion)
+    }
+
+    /// Minimum balance due for rent-exemption of a given account data size.
+    pub fn minimum_balance(&self, data_len: usize) -> u64 {
+        let bytes = data_len as u64;
+        (((ACCOUNT_STORAGE_OVERHEAD + bytes) * self.lamports_per_byte_year) as f64
+            * self.exemption_threshold) as u64
+    }
+
+    /// Whether a given balance and data length would be exempt.
+    pub fn is_exempt(&self, balance: u64, data_len: usize) -> bool {
+        balance >= self.minimum_balance(data_len)
+    }
+
+    /// Creates a `Rent` that charges no lamports.
+    ///
+    /// This is used for testing.
+    pub fn free() -> Self {
+        Self {
+            lamports_per_byte_year: 0,
+            ..Rent::default()
+        }
+    }
+}
+
+/// The default number of slots before an epoch starts to calculate the leader schedule.
+pub const DEFAULT_LEADER_SCHEDULE_SLOT_OFFSET: u64 = DEFAULT_SLOTS_PER_EPOCH;
+
+/// The minimum number of slots per epoch during the warmup period.
+///
+/// Based on `MAX_LOCKOUT_HISTORY` from `vote_program`.
+pub const MINIMUM_SLOTS_PER_EPOCH: u64 = 32;
+
+/// Configuration of epochs and leader schedules.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochSchedule {
+    /// The maximum number of slots in each epoch.
+    pub slots_per_epoch: u64,
+
+    /// A number of slots before beginning of an epoch to calculate
+    /// a leader schedule for that epoch.
+    pub leader_schedule_slot_offset: u64,
+
+    /// Whether epochs start short and grow.
+    pub warmup: bool,
+
+    /// The first epoch after the warmup period.
+    ///
+    /// Basically: `log2(slots_per_epoch) - log2(MINIMUM_SLOTS_PER_EPOCH)`.
+    pub first_normal_epoch: u64,
+
+    /// The first slot after the warmup period.
+    ///
+    /// Basically: `MINIMUM_SLOTS_PER_EPOCH * (2.pow(first_normal_epoch) - 1)`.
+    pub first_normal_slot: u64,
+}
+
+impl Default for EpochSchedule {
+    fn default() -> Self {
+        Self::custom(
+            DEFAULT_SLOTS_PER_EPOCH,
+            DEFAULT_LEADER_SCHEDULE_SLOT_OFFSET,
+            true,
+        )
+    }
+}
+
+impl EpochSchedule {
+    pub fn new(slots_per_epoch: u64) -> Self {
+        Self::custom(slots_per_epoch, slots_per_epoch, true)
+    }
+
+    pub fn without_warmup() -> Self {
+        Self::custom(
+            DEFAULT_SLOTS_PER_EPOCH,
+            DEFAULT_LEADER_SCHEDULE_SLOT_OFFSET,
+            false,
+        )
+    }
+
+    pub fn custom(slots_per_epoch: u64, leader_schedule_slot_offset: u64, warmup: bool) -> Self {
+        assert!(slots_per_epoch >= MINIMUM_SLOTS_PER_EPOCH);
+        let (first_normal_epoch, first_normal_slot) = if warmup {
+            let next_power_of_two = slots_per_epoch.next_power_of_two();
+            let log2_slots_per_epoch = next_power_of_two
+                .trailing_zeros()
+                .saturating_sub(MINIMUM_SLOTS_PER_EPOCH.trailing_zeros());
+
+            (
+                u64::from(log2_slots_per_epoch),
+                next_power_of_two.saturating_sub(MINIMUM_SLOTS_PER_EPOCH),
+            )
+        } else {
+            (0, 0)
+        };
+        EpochSchedule {
+            slots_per_epoch,
+            leader_schedule_slot_offset,
+            warmup,
+            first_normal_epoch,
+            first_normal_slot,
+        }
+    }
+
+    /// get the length of the given epoch (in slots)
+    pub fn get_slots_in_epoch(&self, epoch: u64) -> u64 {
+        if epoch < self.first_normal_epoch {
+            2u64.saturating_pow(
+                (epoch as u32).saturating_add(MINIMUM_SLOTS_PER_EPOCH.trailing_zeros()),
+            )
+        } else {
+            self.slots_per_epoch
+        }
+    }
+
+    /// get the epoch for which the given slot should save off
+    ///  information about stakers
+    pub fn get_leader_schedule_epoch(&self, slot: u64) -> u64 {
+        if slot < self.first_normal_slot {
+            // until we get to normal slots, behave as if leader_schedule_slot_offset == slots_per_epoch
+            self.get_epoch_and_slot_index(slot).0.saturating_add(1)
+        } else {
+            let new_slots_since_first_normal_slot = slot.saturating_sub(self.first_normal_slot);
+            let new_first_normal_leader_schedule_slot =
+                new_slots_since_first_normal_slot.saturating_add(self.leader_schedule_slot_offset);
+            let new_epochs_since_first_normal_leader_schedule =
+                new_first_normal_leader_schedule_slot
+                    .checked_div(self.slots_per_epoch)
+                    .unwrap_or(0);
+            self.first_normal_epoch
+                .saturating_add(new_epochs_since_first_normal_leader_schedule)
+        }
+    }
+
+    /// get epoch for the given slot
+    pub fn get_epoch(&self, slot: u64) -> u64 {
+        self.get_epoch_and_slot_index(slot).0
+    }
+
+    /// get epoch and offset into the epoch for the given slot
+    pub fn get_epoch_and_slot_index(&self, slot: u64) -> (u64, u64) {
+        if slot < self.first_normal_slot {
+            let epoch = slot
+                .saturating_add(MINIMUM_SLOTS_PER_EPOCH)
+                .saturating_add(1)
+                .next_power_of_two()
+                .trailing_zeros()
+                .saturating_sub(MINIMUM_SLOTS_PER_EPOCH.trailing_zeros())
+                .saturating_sub(1);
+
+            let epoch_len =
+                2u64.saturating_pow(epoch.saturating_add(MINIMUM_SLOTS_PER_EPOCH.trailing_zeros()));
+
+            (
+                u64::from(epoch),
+                slot.saturating_sub(epoch_len.saturating_sub(MINIMUM_SLOTS_PER_EPOCH)),
+            )
+        } else {
+            let normal_slot_index = slot.saturating_sub(self.first_normal_slot);
+            let normal_epoch_index = normal_slot_index
+                .checked_div(self.slots_per_epoch)
+                .unwrap_or(0);
+            let epoch = self.first_normal_epoch.saturating_add(normal_epoch_index);
+            let slot_index = normal_slot_index
+                .checked_rem(self.slots_per_epoch)
+                .unwrap_or(0);
+            (epoch, slot_index)
+        }
+    }
+
+    pub fn get_first_slot_in_epoch(&self, epoch: u64) -> u64 {
+        if epoch <= self.first_normal_epoch {
+            2u64.saturating_pow(epoch as u32)
+                .saturating_sub(1)
+                .saturating_mul(MINIMUM_SLOTS_PER_EPOCH)
+        } else {
+            epoch
+                .saturating_sub(self.first_normal_epoch)
+                .saturating_mul(self.slots_per_epoch)
+                .saturating_add(self.first_normal_slot)
+        }
+    }
+
+    pub fn get_last_slot_in_epoch(&self, epoch: u64) -> u64 {
+        self.get_first_slot_in_epoch(epoch)
+            .saturating_add(self.get_slots_in_epoch(epoch))
+            .saturating_sub(1)
+    }
+}
+
+/// Progress of the partitioned epoch rewards distribution.
+///
+/// Aligned to 16 bytes like the program side struct, the bincode encoding has no padding.
+#[repr(C, align(16))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochRewards {
+    /// The starting block height of the rewards distribution in the current
+    /// epoch
+    pub distribution_starting_block_height: u64,
+
+    /// Number of partitions in the rewards distribution in the current epoch,
+    /// used to generate an EpochRewardsHasher
+    pub num_partitions: u64,
+
+    /// The blockhash of the parent block of the first block in the epoch, used
+    /// to seed an EpochRewardsHasher
+    pub parent_blockhash: Hash,
+
+    /// The total rewards points calculated for the current epoch, where points
+    /// equals the sum of (delegated stake * credits observed) for all
+    /// delegations
+    pub total_points: u128,
+
+    /// The total rewards calculated for the current epoch. This may be greater
+    /// than the total `distributed_rewards` at the end of the rewards period,
+    /// due to rounding and inability to deliver rewards smaller than 1 lamport.
+    pub total_rewards: u64,
+
+    /// The rewards currently distributed for the current epoch, in lamports
+    pub distributed_rewards: u64,
+
+    /// Whether the rewards period (including calculation and distribution) is
+    /// active
+    pub active: bool,
+}
+
+impl EpochRewards {
+    pub fn distribute(&mut self, amount: u64) {
+        let new_distributed_rewards = self.distributed_rewards.saturating_add(amount);
+        assert!(new_distributed_rewards <= self.total_rewards);
+        self.distributed_rewards = new_distributed_rewards;
+    }
+}
+
+/// Information about the last restart slot (hard fork).
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastRestartSlot {
+    /// The last restart `Slot`.
+    pub last_restart_slot: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Debug;
+
+    /// Checks `value` against its account data as laid out on mainnet
+    fn assert_layout<T: Sysvar + Debug + PartialEq>(value: T, fixture: &[u8]) {
+        assert_eq!(T::size_of(), fixture.len());
+        assert_eq!(value.to_account_data().unwrap(), fixture);
+        assert_eq!(T::from_account_data(fixture).unwrap(), value);
+    }
+
+    #[test]
+    fn test_clock_layout() {
+        let clock = Clock {
+            slot: 300_000_000,
+            epoch_start_timestamp: 1_717_000_000,
+            epoch: 694,
+            leader_schedule_epoch: 695,
+            unix_timestamp: 1_717_100_000,
+        };
+        #[rustfmt::skip]
+        let fixture = [
+            0, 163, 225, 17, 0, 0, 0, 0, 64, 87, 87, 102, 0, 0, 0, 0,
+            182, 2, 0, 0, 0, 0, 0, 0, 183, 2, 0, 0, 0, 0, 0, 0,
+            224, 221, 88, 102, 0, 0, 0, 0,
+        ];
+        assert_layout(clock, &fixture);
+    }
+
+    #[test]
+    fn test_rent_layout() {
+        let rent = Rent {
+            lamports_per_byte_year: 3480,
+            exemption_threshold: 2.0,
+            burn_percent: 50,
+        };
+        #[rustfmt::skip]
+        let fixture = [
+            152, 13, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 64,
+            50,
+        ];
+        assert_layout(rent, &fixture);
+    }
+
+    #[test]
+    fn test_epoch_schedule_layout() {
+        let epoch_schedule = EpochSchedule::custom(432_000, 432_000, false);
+        #[rustfmt::skip]
+        let fixture = [
+            128, 151, 6, 0, 0, 0, 0, 0, 128, 151, 6, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0,
+        ];
+        assert_layout(epoch_schedule, &fixture);
+    }
+
+    #[test]
+    fn test_epoch_rewards_layout() {
+        let epoch_rewards = EpochRewards {
+            distribution_starting_block_height: 250_000_000,
+            num_partitions: 43,
+            parent_blockhash: Hash([7; 32]),
+            total_points: 123_456_789_012_345_678_901,
+            total_rewards: 1_000_000_000_000,
+            distributed_rewards: 400_000_000_000,
+            active: true,
+        };
+        #[rustfmt::skip]
+        let fixture = [
+            128, 178, 230, 14, 0, 0, 0, 0, 43, 0, 0, 0, 0, 0, 0, 0,
+            7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+            7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+            53, 108, 54, 47, 129, 159, 78, 177, 6, 0, 0, 0, 0, 0, 0, 0,
+            0, 16, 165, 212, 232, 0, 0, 0, 0, 160, 219, 33, 93, 0, 0, 0,
+            1,
+        ];
+        assert_layout(epoch_rewards, &fixture);
+    }
+
+    #[test]
+    fn test_last_restart_slot_layout() {
+        let last_restart_slot = LastRestartSlot {
+            last_restart_slot: 301_000_000,
+        };
+        let fixture = [64, 229, 240, 17, 0, 0, 0, 0];
+        assert_layout(last_restart_slot, &fixture);
+    }
+}
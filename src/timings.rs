@@ -1,7 +1,7 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::num::Saturating;
 
-pub type Pubkey = [u8; 32];
+use crate::transaction_context::Pubkey;
 
 #[derive(Default, Debug, PartialEq, Eq)]
 pub struct ProgramTiming {
@@ -13,6 +13,9 @@ pub struct ProgramTiming {
     pub total_errored_units: Saturating<u64>,
 }
 
+/// Durations are counted in ticks of the [`Clock`](crate::Clock) driving the
+/// [`Measure`](crate::Measure)s that feed them: microseconds for
+/// [`WallClock`](crate::WallClock), work units for [`CycleClock`](crate::CycleClock).
 #[derive(Default, Debug, PartialEq, Eq)]
 pub struct ExecuteDetailsTimings {
     pub serialize_us: Saturating<u64>,
@@ -26,5 +29,57 @@ pub struct ExecuteDetailsTimings {
     pub create_executor_load_elf_us: Saturating<u64>,
     pub create_executor_verify_code_us: Saturating<u64>,
     pub create_executor_jit_compile_us: Saturating<u64>,
-    pub per_program_timings: HashMap<Pubkey, ProgramTiming>,
+    pub per_program_timings: BTreeMap<Pubkey, ProgramTiming>,
+}
+
+impl ExecuteDetailsTimings {
+    /// Adds a finished [`Measure`](crate::Measure) of program execution to `execute_us`.
+    pub fn accumulate_execute(&mut self, measure: crate::Measure) {
+        self.execute_us += measure.end_as_ticks();
+    }
+
+    pub fn accumulate_program(
+        &mut self,
+        program_id: &Pubkey,
+        ticks: u64,
+        compute_units_consumed: u64,
+        is_error: bool,
+    ) {
+        let program_timing = self.per_program_timings.entry(*program_id).or_default();
+        program_timing.accumulated_us += ticks;
+        if is_error {
+            program_timing
+                .errored_txs_compute_consumed
+                .push(compute_units_consumed);
+            program_timing.total_errored_units += compute_units_consumed;
+        } else {
+            program_timing.accumulated_units += compute_units_consumed;
+            program_timing.count += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accumulate_program() {
+        let mut timings = ExecuteDetailsTimings::default();
+        timings.accumulate_program(&[1; 32], 10, 100, false);
+        timings.accumulate_program(&[1; 32], 20, 200, false);
+        timings.accumulate_program(&[1; 32], 5, 50, true);
+        timings.accumulate_program(&[0; 32], 1, 1, false);
+
+        let program_timing = &timings.per_program_timings[&[1; 32]];
+        assert_eq!(program_timing.accumulated_us.0, 35);
+        assert_eq!(program_timing.accumulated_units.0, 300);
+        assert_eq!(program_timing.count.0, 2);
+        assert_eq!(program_timing.errored_txs_compute_consumed, vec![50]);
+        assert_eq!(program_timing.total_errored_units.0, 50);
+        assert_eq!(
+            timings.per_program_timings.keys().collect::<Vec<_>>(),
+            vec![&[0; 32], &[1; 32]]
+        );
+    }
 }
@@ -1,10 +1,31 @@
-use std::{cell::RefCell, pin::Pin, rc::Rc, sync::Arc};
+use std::{
+    cell::{RefCell, RefMut},
+    collections::BTreeSet,
+    pin::Pin,
+    rc::Rc,
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{sysvar::Rent, InstructionError, MAX_PERMITTED_DATA_INCREASE};
 
 pub type Pubkey = [u8; 32];
 pub type Epoch = u64;
 pub type IndexOfAccount = u16;
+/// Maximum permitted size of account data (10 MiB).
+pub const MAX_PERMITTED_DATA_LENGTH: u64 = 10 * 1024 * 1024;
+/// Maximum permitted size of new allocations per transaction, in bytes (20 MiB).
+pub const MAX_PERMITTED_ACCOUNTS_DATA_ALLOCATIONS_PER_TRANSACTION: i64 =
+    MAX_PERMITTED_DATA_LENGTH as i64 * 2;
+
+/// Maximum size of the return data of a transaction, in bytes
+pub const MAX_RETURN_DATA: usize = 1024;
 
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
+/// An account key and the matching account
+pub type TransactionAccount = (Pubkey, AccountSharedData);
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
 pub struct AccountSharedData {
     /// lamports in the account
     lamports: u64,
@@ -18,13 +39,217 @@ pub struct AccountSharedData {
     rent_epoch: Epoch,
 }
 
+/// Read access to the fields of an account
+pub trait ReadableAccount {
+    fn lamports(&self) -> u64;
+    fn data(&self) -> &[u8];
+    fn owner(&self) -> &Pubkey;
+    fn executable(&self) -> bool;
+    fn rent_epoch(&self) -> Epoch;
+}
+
+/// Write access to the fields of an account
+pub trait WritableAccount: ReadableAccount {
+    fn set_lamports(&mut self, lamports: u64);
+    fn checked_add_lamports(&mut self, lamports: u64) -> Result<(), InstructionError> {
+        self.set_lamports(
+            self.lamports()
+                .checked_add(lamports)
+                .ok_or(InstructionError::ArithmeticOverflow)?,
+        );
+        Ok(())
+    }
+    fn checked_sub_lamports(&mut self, lamports: u64) -> Result<(), InstructionError> {
+        self.set_lamports(
+            self.lamports()
+                .checked_sub(lamports)
+                .ok_or(InstructionError::ArithmeticOverflow)?,
+        );
+        Ok(())
+    }
+    fn data_as_mut_slice(&mut self) -> &mut [u8];
+    fn set_owner(&mut self, owner: Pubkey);
+    fn set_executable(&mut self, executable: bool);
+    fn set_rent_epoch(&mut self, epoch: Epoch);
+}
+
+impl ReadableAccount for AccountSharedData {
+    fn lamports(&self) -> u64 {
+        self.lamports
+    }
+
+    fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn owner(&self) -> &Pubkey {
+        &self.owner
+    }
+
+    fn executable(&self) -> bool {
+        self.executable
+    }
+
+    fn rent_epoch(&self) -> Epoch {
+        self.rent_epoch
+    }
+}
+
+impl WritableAccount for AccountSharedData {
+    fn set_lamports(&mut self, lamports: u64) {
+        self.lamports = lamports;
+    }
+
+    fn data_as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.data_mut()[..]
+    }
+
+    fn set_owner(&mut self, owner: Pubkey) {
+        self.owner = owner;
+    }
+
+    fn set_executable(&mut self, executable: bool) {
+        self.executable = executable;
+    }
+
+    fn set_rent_epoch(&mut self, epoch: Epoch) {
+        self.rent_epoch = epoch;
+    }
+}
+
+impl AccountSharedData {
+    pub fn new(lamports: u64, space: usize, owner: &Pubkey) -> Self {
+        Self {
+            lamports,
+            data: Arc::new(vec![0u8; space]),
+            owner: *owner,
+            executable: false,
+            rent_epoch: Epoch::default(),
+        }
+    }
+
+    pub fn create(
+        lamports: u64,
+        data: Vec<u8>,
+        owner: Pubkey,
+        executable: bool,
+        rent_epoch: Epoch,
+    ) -> Self {
+        Self {
+            lamports,
+            data: Arc::new(data),
+            owner,
+            executable,
+            rent_epoch,
+        }
+    }
+
+    /// True if the data buffer is still shared with a clone of this account
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.data) > 1
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        if let Some(data) = Arc::get_mut(&mut self.data) {
+            data.reserve(additional)
+        } else {
+            let mut data = Vec::with_capacity(self.data.len().saturating_add(additional));
+            data.extend_from_slice(&self.data);
+            self.data = Arc::new(data);
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+
+    /// Unshares the data buffer before handing it out, so clones keep their contents
+    fn data_mut(&mut self) -> &mut Vec<u8> {
+        Arc::make_mut(&mut self.data)
+    }
+
+    pub fn resize(&mut self, new_len: usize, value: u8) {
+        self.data_mut().resize(new_len, value)
+    }
+
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        self.data_mut().extend_from_slice(data)
+    }
+
+    pub fn set_data_from_slice(&mut self, new_data: &[u8]) {
+        // If the buffer is shared, replacing it is cheaper than copying it first.
+        let Some(data) = Arc::get_mut(&mut self.data) else {
+            return self.set_data(new_data.to_vec());
+        };
+        data.clear();
+        data.extend_from_slice(new_data);
+    }
+
+    pub fn set_data(&mut self, data: Vec<u8>) {
+        self.data = Arc::new(data);
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct TransactionAccounts {
     accounts: Vec<RefCell<AccountSharedData>>,
     touched_flags: RefCell<Box<[bool]>>,
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+impl TransactionAccounts {
+    fn new(accounts: Vec<RefCell<AccountSharedData>>) -> TransactionAccounts {
+        TransactionAccounts {
+            touched_flags: RefCell::new(vec![false; accounts.len()].into_boxed_slice()),
+            accounts,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn get(&self, index: IndexOfAccount) -> Option<&RefCell<AccountSharedData>> {
+        self.accounts.get(index as usize)
+    }
+
+    pub fn touch(&self, index: IndexOfAccount) -> Result<(), InstructionError> {
+        *self
+            .touched_flags
+            .borrow_mut()
+            .get_mut(index as usize)
+            .ok_or(InstructionError::NotEnoughAccountKeys)? = true;
+        Ok(())
+    }
+
+    pub fn touched_count(&self) -> usize {
+        self.touched_flags
+            .borrow()
+            .iter()
+            .fold(0usize, |accumulator, was_touched| {
+                accumulator.saturating_add(*was_touched as usize)
+            })
+    }
+
+    pub fn try_borrow_mut(
+        &self,
+        index: IndexOfAccount,
+    ) -> Result<RefMut<'_, AccountSharedData>, InstructionError> {
+        self.accounts
+            .get(index as usize)
+            .ok_or(InstructionError::MissingAccount)?
+            .try_borrow_mut()
+            .map_err(|_| InstructionError::AccountBorrowFailed)
+    }
+
+    fn into_accounts_and_touched_flags(self) -> (Vec<AccountSharedData>, Box<[bool]>) {
+        (
+            self.accounts.into_iter().map(RefCell::into_inner).collect(),
+            self.touched_flags.into_inner(),
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TransactionReturnData {
     pub program_id: Pubkey,
     pub data: Vec<u8>,
@@ -57,6 +282,542 @@ pub struct InstructionContext {
     instruction_data: Vec<u8>,
 }
 
+impl InstructionContext {
+    /// Used together with [TransactionContext::get_next_instruction_context]
+    pub fn configure(
+        &mut self,
+        program_accounts: &[IndexOfAccount],
+        instruction_accounts: &[InstructionAccount],
+        instruction_data: &[u8],
+    ) {
+        self.program_accounts = program_accounts.to_vec();
+        self.instruction_accounts = instruction_accounts.to_vec();
+        self.instruction_data = instruction_data.to_vec();
+    }
+
+    /// How many Instructions were on the stack after this one was pushed
+    ///
+    /// That is the number of nested parent Instructions plus one (itself).
+    pub fn get_stack_height(&self) -> usize {
+        self.nesting_level.saturating_add(1)
+    }
+
+    /// Number of program accounts
+    pub fn get_number_of_program_accounts(&self) -> IndexOfAccount {
+        self.program_accounts.len() as IndexOfAccount
+    }
+
+    /// Number of accounts in this Instruction (without program accounts)
+    pub fn get_number_of_instruction_accounts(&self) -> IndexOfAccount {
+        self.instruction_accounts.len() as IndexOfAccount
+    }
+
+    /// Assert that enough accounts were supplied to this Instruction
+    pub fn check_number_of_instruction_accounts(
+        &self,
+        expected_at_least: IndexOfAccount,
+    ) -> Result<(), InstructionError> {
+        if self.get_number_of_instruction_accounts() < expected_at_least {
+            Err(InstructionError::NotEnoughAccountKeys)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Data parameter for the programs `process_instruction` handler
+    pub fn get_instruction_data(&self) -> &[u8] {
+        &self.instruction_data
+    }
+
+    /// Translates the given instruction wide program_account_index into a transaction wide index
+    pub fn get_index_of_program_account_in_transaction(
+        &self,
+        program_account_index: IndexOfAccount,
+    ) -> Result<IndexOfAccount, InstructionError> {
+        Ok(*self
+            .program_accounts
+            .get(program_account_index as usize)
+            .ok_or(InstructionError::NotEnoughAccountKeys)?)
+    }
+
+    /// Translates the given instruction wide instruction_account_index into a transaction wide index
+    pub fn get_index_of_instruction_account_in_transaction(
+        &self,
+        instruction_account_index: IndexOfAccount,
+    ) -> Result<IndexOfAccount, InstructionError> {
+        Ok(self
+            .instruction_accounts
+            .get(instruction_account_index as usize)
+            .ok_or(InstructionError::NotEnoughAccountKeys)?
+            .index_in_transaction)
+    }
+
+    /// Returns `Some(instruction_account_index)` if this is a duplicate
+    /// and `None` if it is the first account with this key
+    pub fn is_instruction_account_duplicate(
+        &self,
+        instruction_account_index: IndexOfAccount,
+    ) -> Result<Option<IndexOfAccount>, InstructionError> {
+        let index_in_callee = self
+            .instruction_accounts
+            .get(instruction_account_index as usize)
+            .ok_or(InstructionError::NotEnoughAccountKeys)?
+            .index_in_callee;
+        Ok(if index_in_callee == instruction_account_index {
+            None
+        } else {
+            Some(index_in_callee)
+        })
+    }
+
+    /// Gets the key of the last program account of this Instruction
+    pub fn get_last_program_key<'a, 'b: 'a>(
+        &'a self,
+        transaction_context: &'b TransactionContext,
+    ) -> Result<&'b Pubkey, InstructionError> {
+        self.get_index_of_program_account_in_transaction(
+            self.get_number_of_program_accounts().saturating_sub(1),
+        )
+        .and_then(|index_in_transaction| {
+            transaction_context.get_key_of_account_at_index(index_in_transaction)
+        })
+    }
+
+    fn try_borrow_account<'a, 'b: 'a>(
+        &'a self,
+        transaction_context: &'b TransactionContext,
+        index_in_transaction: IndexOfAccount,
+        index_in_instruction: IndexOfAccount,
+    ) -> Result<BorrowedAccount<'a>, InstructionError> {
+        let account = transaction_context
+            .accounts
+            .try_borrow_mut(index_in_transaction)?;
+        Ok(BorrowedAccount {
+            transaction_context,
+            instruction_context: self,
+            index_in_transaction,
+            index_in_instruction,
+            account,
+        })
+    }
+
+    /// Gets the last program account of this Instruction
+    pub fn try_borrow_last_program_account<'a, 'b: 'a>(
+        &'a self,
+        transaction_context: &'b TransactionContext,
+    ) -> Result<BorrowedAccount<'a>, InstructionError> {
+        self.try_borrow_program_account(
+            transaction_context,
+            self.get_number_of_program_accounts().saturating_sub(1),
+        )
+    }
+
+    /// Tries to borrow a program account from this Instruction
+    pub fn try_borrow_program_account<'a, 'b: 'a>(
+        &'a self,
+        transaction_context: &'b TransactionContext,
+        program_account_index: IndexOfAccount,
+    ) -> Result<BorrowedAccount<'a>, InstructionError> {
+        let index_in_transaction =
+            self.get_index_of_program_account_in_transaction(program_account_index)?;
+        self.try_borrow_account(
+            transaction_context,
+            index_in_transaction,
+            program_account_index,
+        )
+    }
+
+    /// Gets an instruction account of this Instruction
+    pub fn try_borrow_instruction_account<'a, 'b: 'a>(
+        &'a self,
+        transaction_context: &'b TransactionContext,
+        instruction_account_index: IndexOfAccount,
+    ) -> Result<BorrowedAccount<'a>, InstructionError> {
+        let index_in_transaction =
+            self.get_index_of_instruction_account_in_transaction(instruction_account_index)?;
+        self.try_borrow_account(
+            transaction_context,
+            index_in_transaction,
+            self.get_number_of_program_accounts()
+                .saturating_add(instruction_account_index),
+        )
+    }
+
+    /// Returns whether an instruction account is a signer
+    pub fn is_instruction_account_signer(
+        &self,
+        instruction_account_index: IndexOfAccount,
+    ) -> Result<bool, InstructionError> {
+        Ok(self
+            .instruction_accounts
+            .get(instruction_account_index as usize)
+            .ok_or(InstructionError::MissingAccount)?
+            .is_signer)
+    }
+
+    /// Returns whether an instruction account is writable
+    pub fn is_instruction_account_writable(
+        &self,
+        instruction_account_index: IndexOfAccount,
+    ) -> Result<bool, InstructionError> {
+        Ok(self
+            .instruction_accounts
+            .get(instruction_account_index as usize)
+            .ok_or(InstructionError::MissingAccount)?
+            .is_writable)
+    }
+
+    /// Calculates the set of all keys of signer instruction accounts in this Instruction
+    pub fn get_signers(
+        &self,
+        transaction_context: &TransactionContext,
+    ) -> Result<BTreeSet<Pubkey>, InstructionError> {
+        let mut result = BTreeSet::new();
+        for instruction_account in self.instruction_accounts.iter() {
+            if instruction_account.is_signer {
+                result.insert(
+                    *transaction_context
+                        .get_key_of_account_at_index(instruction_account.index_in_transaction)?,
+                );
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Shared account borrowed from the TransactionContext and an InstructionContext.
+///
+/// Every mutation is checked against the ownership rules of the runtime and marks
+/// the account as touched.
+#[derive(Debug)]
+pub struct BorrowedAccount<'a> {
+    transaction_context: &'a TransactionContext,
+    instruction_context: &'a InstructionContext,
+    index_in_transaction: IndexOfAccount,
+    index_in_instruction: IndexOfAccount,
+    account: RefMut<'a, AccountSharedData>,
+}
+
+impl BorrowedAccount<'_> {
+    /// Returns the index of this account (transaction wide)
+    pub fn get_index_in_transaction(&self) -> IndexOfAccount {
+        self.index_in_transaction
+    }
+
+    /// Returns the public key of this account (transaction wide)
+    pub fn get_key(&self) -> &Pubkey {
+        self.transaction_context
+            .get_key_of_account_at_index(self.index_in_transaction)
+            .unwrap()
+    }
+
+    /// Returns the owner of this account (transaction wide)
+    pub fn get_owner(&self) -> &Pubkey {
+        self.account.owner()
+    }
+
+    /// Assigns the owner of this account (transaction wide)
+    pub fn set_owner(&mut self, pubkey: &Pubkey) -> Result<(), InstructionError> {
+        // Only the owner can assign a new owner
+        if !self.is_owned_by_current_program() {
+            return Err(InstructionError::ModifiedProgramId);
+        }
+        // and only if the account is writable
+        if !self.is_writable() {
+            return Err(InstructionError::ModifiedProgramId);
+        }
+        // and only if the account is not executable
+        if self.is_executable() {
+            return Err(InstructionError::ModifiedProgramId);
+        }
+        // and only if the data is zero-initialized or empty
+        if !is_zeroed(self.get_data()) {
+            return Err(InstructionError::ModifiedProgramId);
+        }
+        // don't touch the account if the owner does not change
+        if self.get_owner() == pubkey {
+            return Ok(());
+        }
+        self.touch()?;
+        self.account.set_owner(*pubkey);
+        Ok(())
+    }
+
+    /// Returns the number of lamports of this account (transaction wide)
+    pub fn get_lamports(&self) -> u64 {
+        self.account.lamports()
+    }
+
+    /// Overwrites the number of lamports of this account (transaction wide)
+    pub fn set_lamports(&mut self, lamports: u64) -> Result<(), InstructionError> {
+        // An account not owned by the program cannot have its balance decrease
+        if !self.is_owned_by_current_program() && lamports < self.get_lamports() {
+            return Err(InstructionError::ExternalAccountLamportSpend);
+        }
+        // The balance of read-only may not change
+        if !self.is_writable() {
+            return Err(InstructionError::ReadonlyLamportChange);
+        }
+        // The balance of executable accounts may not change
+        if self.is_executable() {
+            return Err(InstructionError::ExecutableLamportChange);
+        }
+        // don't touch the account if the lamports do not change
+        if self.get_lamports() == lamports {
+            return Ok(());
+        }
+        self.touch()?;
+        self.account.set_lamports(lamports);
+        Ok(())
+    }
+
+    /// Adds lamports to this account (transaction wide)
+    pub fn checked_add_lamports(&mut self, lamports: u64) -> Result<(), InstructionError> {
+        self.set_lamports(
+            self.get_lamports()
+                .checked_add(lamports)
+                .ok_or(InstructionError::ArithmeticOverflow)?,
+        )
+    }
+
+    /// Subtracts lamports from this account (transaction wide)
+    pub fn checked_sub_lamports(&mut self, lamports: u64) -> Result<(), InstructionError> {
+        self.set_lamports(
+            self.get_lamports()
+                .checked_sub(lamports)
+                .ok_or(InstructionError::ArithmeticOverflow)?,
+        )
+    }
+
+    /// Returns a read-only slice of the account data (transaction wide)
+    pub fn get_data(&self) -> &[u8] {
+        self.account.data()
+    }
+
+    /// Returns a writable slice of the account data (transaction wide)
+    pub fn get_data_mut(&mut self) -> Result<&mut [u8], InstructionError> {
+        self.can_data_be_changed()?;
+        self.touch()?;
+        self.make_data_mut();
+        Ok(self.account.data_as_mut_slice())
+    }
+
+    /// Overwrites the account data and size (transaction wide)
+    pub fn set_data_from_slice(&mut self, data: &[u8]) -> Result<(), InstructionError> {
+        self.can_data_be_resized(data.len())?;
+        self.can_data_be_changed()?;
+        self.touch()?;
+        self.update_accounts_resize_delta(data.len())?;
+        // No make_data_mut() here, the content is replaced anyway.
+        self.account.set_data_from_slice(data);
+        Ok(())
+    }
+
+    /// Resizes the account data (transaction wide)
+    ///
+    /// Fills it with zeros at the end if is extended or truncates at the end otherwise.
+    pub fn set_data_length(&mut self, new_length: usize) -> Result<(), InstructionError> {
+        self.can_data_be_resized(new_length)?;
+        self.can_data_be_changed()?;
+        // don't touch the account if the length does not change
+        if self.get_data().len() == new_length {
+            return Ok(());
+        }
+        self.touch()?;
+        self.update_accounts_resize_delta(new_length)?;
+        self.account.resize(new_length, 0);
+        Ok(())
+    }
+
+    /// Appends all elements in a slice to the account
+    pub fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), InstructionError> {
+        let new_len = self.get_data().len().saturating_add(data.len());
+        self.can_data_be_resized(new_len)?;
+        self.can_data_be_changed()?;
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.touch()?;
+        self.update_accounts_resize_delta(new_len)?;
+        self.make_data_mut();
+        self.account.extend_from_slice(data);
+        Ok(())
+    }
+
+    /// Reserves capacity for at least additional more bytes, without touching the account
+    pub fn reserve(&mut self, additional: usize) -> Result<(), InstructionError> {
+        self.make_data_mut();
+        self.account.reserve(additional);
+        Ok(())
+    }
+
+    /// Returns whether the underlying AccountSharedData is shared.
+    pub fn is_shared(&self) -> bool {
+        self.account.is_shared()
+    }
+
+    fn make_data_mut(&mut self) {
+        // On the first write, unshare with MAX_PERMITTED_DATA_INCREASE extra capacity
+        // so that a later realloc does not have to copy the data a second time.
+        if self.account.is_shared() {
+            self.account.reserve(MAX_PERMITTED_DATA_INCREASE);
+        }
+    }
+
+    /// Returns whether this account is executable (transaction wide)
+    pub fn is_executable(&self) -> bool {
+        self.account.executable()
+    }
+
+    /// Configures whether this account is executable (transaction wide)
+    pub fn set_executable(&mut self, is_executable: bool) -> Result<(), InstructionError> {
+        // To become executable an account must be rent exempt
+        if !self
+            .transaction_context
+            .rent
+            .is_exempt(self.get_lamports(), self.get_data().len())
+        {
+            return Err(InstructionError::ExecutableAccountNotRentExempt);
+        }
+        // Only the owner can set the executable flag
+        if !self.is_owned_by_current_program() {
+            return Err(InstructionError::ExecutableModified);
+        }
+        // and only if the account is writable
+        if !self.is_writable() {
+            return Err(InstructionError::ExecutableModified);
+        }
+        // one can not clear the executable flag
+        if self.is_executable() && !is_executable {
+            return Err(InstructionError::ExecutableModified);
+        }
+        // don't touch the account if the executable flag does not change
+        if self.is_executable() == is_executable {
+            return Ok(());
+        }
+        self.touch()?;
+        self.account.set_executable(is_executable);
+        Ok(())
+    }
+
+    /// Returns the rent epoch of this account (transaction wide)
+    pub fn get_rent_epoch(&self) -> u64 {
+        self.account.rent_epoch()
+    }
+
+    /// Programs may not change the rent epoch, only an unchanged value is accepted
+    pub fn set_rent_epoch(&mut self, rent_epoch: Epoch) -> Result<(), InstructionError> {
+        if self.get_rent_epoch() != rent_epoch {
+            return Err(InstructionError::RentEpochModified);
+        }
+        Ok(())
+    }
+
+    /// Returns whether this account is a signer (instruction wide)
+    pub fn is_signer(&self) -> bool {
+        if self.index_in_instruction < self.instruction_context.get_number_of_program_accounts() {
+            return false;
+        }
+        self.instruction_context
+            .is_instruction_account_signer(
+                self.index_in_instruction
+                    .saturating_sub(self.instruction_context.get_number_of_program_accounts()),
+            )
+            .unwrap_or_default()
+    }
+
+    /// Returns whether this account is writable (instruction wide)
+    pub fn is_writable(&self) -> bool {
+        if self.index_in_instruction < self.instruction_context.get_number_of_program_accounts() {
+            return false;
+        }
+        self.instruction_context
+            .is_instruction_account_writable(
+                self.index_in_instruction
+                    .saturating_sub(self.instruction_context.get_number_of_program_accounts()),
+            )
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the owner of this account is the current `InstructionContext`s last program (instruction wide)
+    pub fn is_owned_by_current_program(&self) -> bool {
+        self.instruction_context
+            .get_last_program_key(self.transaction_context)
+            .map(|key| key == self.get_owner())
+            .unwrap_or_default()
+    }
+
+    /// Returns an error if the account data can not be mutated by the current program
+    pub fn can_data_be_changed(&self) -> Result<(), InstructionError> {
+        // Only non-executable accounts data can be changed
+        if self.is_executable() {
+            return Err(InstructionError::ExecutableDataModified);
+        }
+        // and only if the account is writable
+        if !self.is_writable() {
+            return Err(InstructionError::ReadonlyDataModified);
+        }
+        // and only if we are the owner
+        if !self.is_owned_by_current_program() {
+            return Err(InstructionError::ExternalAccountDataModified);
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the account data can not be resized to the given length
+    pub fn can_data_be_resized(&self, new_length: usize) -> Result<(), InstructionError> {
+        let old_length = self.get_data().len();
+        // Only the owner can change the length of the data
+        if new_length != old_length && !self.is_owned_by_current_program() {
+            return Err(InstructionError::AccountDataSizeChanged);
+        }
+        // The new length can not exceed the maximum permitted length
+        if new_length > MAX_PERMITTED_DATA_LENGTH as usize {
+            return Err(InstructionError::InvalidRealloc);
+        }
+        // The resize can not exceed the per-transaction maximum
+        let length_delta = (new_length as i64).saturating_sub(old_length as i64);
+        if self
+            .transaction_context
+            .accounts_resize_delta()?
+            .saturating_add(length_delta)
+            > MAX_PERMITTED_ACCOUNTS_DATA_ALLOCATIONS_PER_TRANSACTION
+        {
+            return Err(InstructionError::MaxAccountsDataAllocationsExceeded);
+        }
+        Ok(())
+    }
+
+    fn touch(&self) -> Result<(), InstructionError> {
+        self.transaction_context
+            .accounts
+            .touch(self.index_in_transaction)
+    }
+
+    fn update_accounts_resize_delta(&mut self, new_len: usize) -> Result<(), InstructionError> {
+        let mut accounts_resize_delta = self
+            .transaction_context
+            .accounts_resize_delta
+            .try_borrow_mut()
+            .map_err(|_| InstructionError::GenericError)?;
+        *accounts_resize_delta = accounts_resize_delta
+            .saturating_add((new_len as i64).saturating_sub(self.get_data().len() as i64));
+        Ok(())
+    }
+}
+
+fn is_zeroed(buf: &[u8]) -> bool {
+    const ZEROS_LEN: usize = 1024;
+    const ZEROS: [u8; ZEROS_LEN] = [0; ZEROS_LEN];
+    let mut chunks = buf.chunks_exact(ZEROS_LEN);
+
+    #[allow(clippy::indexing_slicing)]
+    {
+        chunks.all(|chunk| chunk == &ZEROS[..])
+            && chunks.remainder() == &ZEROS[..chunks.remainder().len()]
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TransactionContext {
     account_keys: Pin<Box<[Pubkey]>>,
@@ -67,4 +828,681 @@ pub struct TransactionContext {
     instruction_trace: Vec<InstructionContext>,
     return_data: TransactionReturnData,
     accounts_resize_delta: RefCell<i64>,
+    rent: Rent,
+}
+
+impl TransactionContext {
+    /// The capacities are usually the [ComputeBudget](crate::ComputeBudget)'s
+    /// `max_instruction_stack_depth` and `max_instruction_trace_length`. `rent` decides
+    /// whether an account is exempt enough to be made executable.
+    pub fn new(
+        transaction_accounts: Vec<TransactionAccount>,
+        rent: Rent,
+        instruction_stack_capacity: usize,
+        instruction_trace_capacity: usize,
+    ) -> Self {
+        let (account_keys, accounts): (Vec<_>, Vec<_>) = transaction_accounts
+            .into_iter()
+            .map(|(key, account)| (key, RefCell::new(account)))
+            .unzip();
+        Self {
+            account_keys: Pin::new(account_keys.into_boxed_slice()),
+            accounts: Rc::new(TransactionAccounts::new(accounts)),
+            instruction_stack_capacity,
+            instruction_trace_capacity,
+            instruction_stack: Vec::with_capacity(instruction_stack_capacity),
+            instruction_trace: vec![InstructionContext::default()],
+            return_data: TransactionReturnData::default(),
+            accounts_resize_delta: RefCell::new(0),
+            rent,
+        }
+    }
+
+    pub fn accounts(&self) -> &Rc<TransactionAccounts> {
+        &self.accounts
+    }
+
+    /// Consumes the context and returns the post-execution state
+    ///
+    /// Fails if a `BorrowedAccount` or another clone of the accounts is still alive.
+    pub fn deconstruct(self) -> Result<ExecutionRecord, InstructionError> {
+        let accounts = Rc::try_unwrap(self.accounts)
+            .map_err(|_| InstructionError::AccountBorrowOutstanding)?;
+        let (accounts, touched_flags) = accounts.into_accounts_and_touched_flags();
+        let account_keys = Vec::from(Pin::into_inner(self.account_keys));
+        let touched_accounts = account_keys
+            .iter()
+            .zip(touched_flags.iter())
+            .filter(|(_, was_touched)| **was_touched)
+            .map(|(key, _)| *key)
+            .collect();
+        Ok(ExecutionRecord {
+            accounts: account_keys.into_iter().zip(accounts).collect(),
+            touched_accounts,
+            return_data: self.return_data,
+            accounts_resize_delta: self.accounts_resize_delta.into_inner(),
+        })
+    }
+
+    pub fn get_number_of_accounts(&self) -> IndexOfAccount {
+        self.accounts.len() as IndexOfAccount
+    }
+
+    /// Searches for an account by its key
+    pub fn get_key_of_account_at_index(
+        &self,
+        index_in_transaction: IndexOfAccount,
+    ) -> Result<&Pubkey, InstructionError> {
+        self.account_keys
+            .get(index_in_transaction as usize)
+            .ok_or(InstructionError::NotEnoughAccountKeys)
+    }
+
+    /// Searches for an account by its key
+    pub fn get_account_at_index(
+        &self,
+        index_in_transaction: IndexOfAccount,
+    ) -> Result<&RefCell<AccountSharedData>, InstructionError> {
+        self.accounts
+            .get(index_in_transaction)
+            .ok_or(InstructionError::NotEnoughAccountKeys)
+    }
+
+    /// Searches for an account by its key
+    pub fn find_index_of_account(&self, pubkey: &Pubkey) -> Option<IndexOfAccount> {
+        self.account_keys
+            .iter()
+            .position(|key| key == pubkey)
+            .map(|index| index as IndexOfAccount)
+    }
+
+    /// Gets the max length of the InstructionContext trace
+    pub fn get_instruction_trace_capacity(&self) -> usize {
+        self.instruction_trace_capacity
+    }
+
+    /// Returns the instruction trace length.
+    ///
+    /// Not counting the last empty InstructionContext which is always pre-reserved for the next instruction.
+    /// See also `get_next_instruction_context()`.
+    pub fn get_instruction_trace_length(&self) -> usize {
+        self.instruction_trace.len().saturating_sub(1)
+    }
+
+    /// Gets an InstructionContext by its index in the trace
+    pub fn get_instruction_context_at_index_in_trace(
+        &self,
+        index_in_trace: usize,
+    ) -> Result<&InstructionContext, InstructionError> {
+        self.instruction_trace
+            .get(index_in_trace)
+            .ok_or(InstructionError::CallDepth)
+    }
+
+    /// Gets an InstructionContext by its nesting level in the stack
+    pub fn get_instruction_context_at_nesting_level(
+        &self,
+        nesting_level: usize,
+    ) -> Result<&InstructionContext, InstructionError> {
+        let index_in_trace = *self
+            .instruction_stack
+            .get(nesting_level)
+            .ok_or(InstructionError::CallDepth)?;
+        let instruction_context = self.get_instruction_context_at_index_in_trace(index_in_trace)?;
+        debug_assert_eq!(instruction_context.nesting_level, nesting_level);
+        Ok(instruction_context)
+    }
+
+    /// Gets the max height of the InstructionContext stack
+    pub fn get_instruction_stack_capacity(&self) -> usize {
+        self.instruction_stack_capacity
+    }
+
+    /// Gets instruction stack height, top-level instructions are height 1
+    pub fn get_instruction_context_stack_height(&self) -> usize {
+        self.instruction_stack.len()
+    }
+
+    /// Returns the current InstructionContext
+    pub fn get_current_instruction_context(&self) -> Result<&InstructionContext, InstructionError> {
+        let level = self
+            .get_instruction_context_stack_height()
+            .checked_sub(1)
+            .ok_or(InstructionError::CallDepth)?;
+        self.get_instruction_context_at_nesting_level(level)
+    }
+
+    /// Returns the InstructionContext to configure for the next invocation.
+    ///
+    /// The last InstructionContext is always empty and pre-reserved for the next instruction.
+    pub fn get_next_instruction_context(
+        &mut self,
+    ) -> Result<&mut InstructionContext, InstructionError> {
+        self.instruction_trace
+            .last_mut()
+            .ok_or(InstructionError::CallDepth)
+    }
+
+    /// Pushes the next InstructionContext
+    pub fn push(&mut self) -> Result<(), InstructionError> {
+        let nesting_level = self.get_instruction_context_stack_height();
+        let caller_instruction_context = self
+            .instruction_trace
+            .last()
+            .ok_or(InstructionError::CallDepth)?;
+        let callee_instruction_accounts_lamport_sum =
+            self.instruction_accounts_lamport_sum(caller_instruction_context)?;
+        if !self.instruction_stack.is_empty() {
+            let caller_instruction_context = self.get_current_instruction_context()?;
+            let original_caller_instruction_accounts_lamport_sum =
+                caller_instruction_context.instruction_accounts_lamport_sum;
+            let current_caller_instruction_accounts_lamport_sum =
+                self.instruction_accounts_lamport_sum(caller_instruction_context)?;
+            if original_caller_instruction_accounts_lamport_sum
+                != current_caller_instruction_accounts_lamport_sum
+            {
+                return Err(InstructionError::UnbalancedInstruction);
+            }
+        }
+        {
+            let instruction_context = self.get_next_instruction_context()?;
+            instruction_context.nesting_level = nesting_level;
+            instruction_context.instruction_accounts_lamport_sum =
+                callee_instruction_accounts_lamport_sum;
+        }
+        let index_in_trace = self.get_instruction_trace_length();
+        if index_in_trace >= self.instruction_trace_capacity {
+            return Err(InstructionError::MaxInstructionTraceLengthExceeded);
+        }
+        self.instruction_trace.push(InstructionContext::default());
+        if nesting_level >= self.instruction_stack_capacity {
+            return Err(InstructionError::CallDepth);
+        }
+        self.instruction_stack.push(index_in_trace);
+        Ok(())
+    }
+
+    /// Pops the current InstructionContext
+    pub fn pop(&mut self) -> Result<(), InstructionError> {
+        if self.instruction_stack.is_empty() {
+            return Err(InstructionError::CallDepth);
+        }
+        // Verify (before we pop) that the total sum of all lamports in this instruction did not change
+        let detected_an_unbalanced_instruction =
+            self.get_current_instruction_context()
+                .and_then(|instruction_context| {
+                    // Verify all executable accounts have no outstanding refs
+                    for account_index in instruction_context.program_accounts.iter() {
+                        self.get_account_at_index(*account_index)?
+                            .try_borrow_mut()
+                            .map_err(|_| InstructionError::AccountBorrowOutstanding)?;
+                    }
+                    self.instruction_accounts_lamport_sum(instruction_context)
+                        .map(|instruction_accounts_lamport_sum| {
+                            instruction_context.instruction_accounts_lamport_sum
+                                != instruction_accounts_lamport_sum
+                        })
+                });
+        // Always pop, even if we `detected_an_unbalanced_instruction`
+        self.instruction_stack.pop();
+        if detected_an_unbalanced_instruction? {
+            Err(InstructionError::UnbalancedInstruction)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Gets the return data of the current instruction or any above
+    pub fn get_return_data(&self) -> (&Pubkey, &[u8]) {
+        (&self.return_data.program_id, &self.return_data.data)
+    }
+
+    /// Set the return data of the current instruction
+    pub fn set_return_data(
+        &mut self,
+        program_id: Pubkey,
+        data: Vec<u8>,
+    ) -> Result<(), InstructionError> {
+        self.return_data = TransactionReturnData { program_id, data };
+        Ok(())
+    }
+
+    /// Returns the accounts resize delta
+    pub fn accounts_resize_delta(&self) -> Result<i64, InstructionError> {
+        self.accounts_resize_delta
+            .try_borrow()
+            .map_err(|_| InstructionError::GenericError)
+            .map(|value_ref| *value_ref)
+    }
+
+    /// Calculates the sum of all lamports within an instruction
+    fn instruction_accounts_lamport_sum(
+        &self,
+        instruction_context: &InstructionContext,
+    ) -> Result<u128, InstructionError> {
+        let mut instruction_accounts_lamport_sum: u128 = 0;
+        for instruction_account_index in 0..instruction_context.get_number_of_instruction_accounts()
+        {
+            if instruction_context
+                .is_instruction_account_duplicate(instruction_account_index)?
+                .is_some()
+            {
+                continue; // Skip duplicate account
+            }
+            let index_in_transaction = instruction_context
+                .get_index_of_instruction_account_in_transaction(instruction_account_index)?;
+            instruction_accounts_lamport_sum = (self
+                .get_account_at_index(index_in_transaction)?
+                .try_borrow()
+                .map_err(|_| InstructionError::AccountBorrowOutstanding)?
+                .lamports() as u128)
+                .checked_add(instruction_accounts_lamport_sum)
+                .ok_or(InstructionError::ArithmeticOverflow)?;
+        }
+        Ok(instruction_accounts_lamport_sum)
+    }
+}
+
+/// Post-execution state of a transaction, committed as the public outputs of a proof
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    /// Accounts after execution, in the order of the transaction's account keys
+    pub accounts: Vec<TransactionAccount>,
+    /// Keys of the accounts which were modified during execution
+    pub touched_accounts: BTreeSet<Pubkey>,
+    pub return_data: TransactionReturnData,
+    pub accounts_resize_delta: i64,
+}
+
+impl TryFrom<TransactionContext> for ExecutionRecord {
+    type Error = InstructionError;
+
+    fn try_from(transaction_context: TransactionContext) -> Result<Self, Self::Error> {
+        transaction_context.deconstruct()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instruction_account(index_in_transaction: IndexOfAccount) -> InstructionAccount {
+        InstructionAccount {
+            index_in_transaction,
+            index_in_caller: index_in_transaction,
+            index_in_callee: index_in_transaction - 1,
+            is_signer: false,
+            is_writable: true,
+        }
+    }
+
+    /// A program at index 0 and two accounts of 100 lamports each
+    fn transaction_context_with_accounts(
+        instruction_stack_capacity: usize,
+        instruction_trace_capacity: usize,
+    ) -> TransactionContext {
+        let account = AccountSharedData {
+            lamports: 100,
+            ..AccountSharedData::default()
+        };
+        TransactionContext::new(
+            vec![
+                ([0; 32], AccountSharedData::default()),
+                ([1; 32], account.clone()),
+                ([2; 32], account),
+            ],
+            Rent::default(),
+            instruction_stack_capacity,
+            instruction_trace_capacity,
+        )
+    }
+
+    fn push(transaction_context: &mut TransactionContext) -> Result<(), InstructionError> {
+        transaction_context
+            .get_next_instruction_context()?
+            .configure(&[0], &[instruction_account(1), instruction_account(2)], &[]);
+        transaction_context.push()
+    }
+
+    fn set_lamports(
+        transaction_context: &TransactionContext,
+        index: IndexOfAccount,
+        lamports: u64,
+    ) {
+        transaction_context
+            .get_account_at_index(index)
+            .unwrap()
+            .borrow_mut()
+            .lamports = lamports;
+    }
+
+    #[test]
+    fn test_push_pop_call_depth() {
+        let mut transaction_context = transaction_context_with_accounts(2, 8);
+        push(&mut transaction_context).unwrap();
+        push(&mut transaction_context).unwrap();
+        assert_eq!(
+            push(&mut transaction_context),
+            Err(InstructionError::CallDepth)
+        );
+        assert_eq!(
+            transaction_context.get_instruction_context_stack_height(),
+            2
+        );
+        transaction_context.pop().unwrap();
+        transaction_context.pop().unwrap();
+        assert_eq!(transaction_context.pop(), Err(InstructionError::CallDepth));
+    }
+
+    #[test]
+    fn test_push_max_instruction_trace_length() {
+        let mut transaction_context = transaction_context_with_accounts(5, 2);
+        push(&mut transaction_context).unwrap();
+        transaction_context.pop().unwrap();
+        push(&mut transaction_context).unwrap();
+        transaction_context.pop().unwrap();
+        assert_eq!(
+            push(&mut transaction_context),
+            Err(InstructionError::MaxInstructionTraceLengthExceeded)
+        );
+        assert_eq!(transaction_context.get_instruction_trace_length(), 2);
+    }
+
+    #[test]
+    fn test_unbalanced_instruction() {
+        let mut transaction_context = transaction_context_with_accounts(5, 8);
+
+        // Moving lamports between the instruction accounts keeps the sum
+        push(&mut transaction_context).unwrap();
+        set_lamports(&transaction_context, 1, 50);
+        set_lamports(&transaction_context, 2, 150);
+        transaction_context.pop().unwrap();
+
+        // Minting lamports is detected when the instruction returns
+        push(&mut transaction_context).unwrap();
+        set_lamports(&transaction_context, 1, 51);
+        assert_eq!(
+            transaction_context.pop(),
+            Err(InstructionError::UnbalancedInstruction)
+        );
+        assert_eq!(
+            transaction_context.get_instruction_context_stack_height(),
+            0
+        );
+
+        // and when the caller invokes another instruction
+        push(&mut transaction_context).unwrap();
+        set_lamports(&transaction_context, 2, 0);
+        assert_eq!(
+            push(&mut transaction_context),
+            Err(InstructionError::UnbalancedInstruction)
+        );
+    }
+
+    const PROGRAM_ID: Pubkey = [0; 32];
+    const OTHER_PROGRAM_ID: Pubkey = [8; 32];
+    const EXEMPT_LAMPORTS: u64 = 1_000_000_000;
+
+    /// Runs `f` on instruction account `instruction_account_index` of an instruction of
+    /// [PROGRAM_ID] over:
+    /// 0. a writable account owned by the program, with zeroed data
+    /// 1. a writable account owned by another program
+    /// 2. a readonly account owned by the program
+    /// 3. a writable executable account owned by the program
+    /// 4. a writable account owned by the program that is not rent exempt
+    fn with_borrowed_account<R>(
+        instruction_account_index: IndexOfAccount,
+        f: impl FnOnce(&mut BorrowedAccount) -> R,
+    ) -> (R, TransactionContext) {
+        let mut transaction_context = TransactionContext::new(
+            vec![
+                (PROGRAM_ID, AccountSharedData::default()),
+                (
+                    [1; 32],
+                    AccountSharedData::new(EXEMPT_LAMPORTS, 2, &PROGRAM_ID),
+                ),
+                (
+                    [2; 32],
+                    AccountSharedData::create(
+                        EXEMPT_LAMPORTS,
+                        vec![1, 2],
+                        OTHER_PROGRAM_ID,
+                        false,
+                        0,
+                    ),
+                ),
+                (
+                    [3; 32],
+                    AccountSharedData::new(EXEMPT_LAMPORTS, 2, &PROGRAM_ID),
+                ),
+                (
+                    [4; 32],
+                    AccountSharedData::create(EXEMPT_LAMPORTS, vec![1, 2], PROGRAM_ID, true, 0),
+                ),
+                ([5; 32], AccountSharedData::new(1, 2, &PROGRAM_ID)),
+            ],
+            Rent::default(),
+            1,
+            1,
+        );
+        let mut instruction_accounts: Vec<_> = (1..6).map(instruction_account).collect();
+        instruction_accounts[2].is_writable = false;
+        transaction_context
+            .get_next_instruction_context()
+            .unwrap()
+            .configure(&[0], &instruction_accounts, &[]);
+        transaction_context.push().unwrap();
+
+        let instruction_context = transaction_context
+            .get_current_instruction_context()
+            .unwrap();
+        let mut borrowed_account = instruction_context
+            .try_borrow_instruction_account(&transaction_context, instruction_account_index)
+            .unwrap();
+        let result = f(&mut borrowed_account);
+        drop(borrowed_account);
+        (result, transaction_context)
+    }
+
+    #[test]
+    fn test_external_account_lamport_spend() {
+        let (result, _) = with_borrowed_account(1, |account| {
+            account.checked_add_lamports(1)?;
+            account.checked_sub_lamports(1)
+        });
+        assert_eq!(result, Err(InstructionError::ExternalAccountLamportSpend));
+    }
+
+    #[test]
+    fn test_readonly_lamport_change() {
+        let (result, _) = with_borrowed_account(2, |account| account.checked_add_lamports(1));
+        assert_eq!(result, Err(InstructionError::ReadonlyLamportChange));
+    }
+
+    #[test]
+    fn test_executable_lamport_change() {
+        let (result, _) = with_borrowed_account(3, |account| account.checked_add_lamports(1));
+        assert_eq!(result, Err(InstructionError::ExecutableLamportChange));
+    }
+
+    #[test]
+    fn test_modified_program_id() {
+        for instruction_account_index in [1, 2, 3] {
+            let (result, _) = with_borrowed_account(instruction_account_index, |account| {
+                account.set_owner(&OTHER_PROGRAM_ID)
+            });
+            assert_eq!(result, Err(InstructionError::ModifiedProgramId));
+        }
+        let (result, _) = with_borrowed_account(0, |account| {
+            account.set_data_from_slice(&[0, 1])?;
+            account.set_owner(&OTHER_PROGRAM_ID)
+        });
+        assert_eq!(result, Err(InstructionError::ModifiedProgramId));
+
+        let (result, transaction_context) =
+            with_borrowed_account(0, |account| account.set_owner(&OTHER_PROGRAM_ID));
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            transaction_context
+                .get_account_at_index(1)
+                .unwrap()
+                .borrow()
+                .owner(),
+            &OTHER_PROGRAM_ID
+        );
+    }
+
+    #[test]
+    fn test_executable_account_not_rent_exempt() {
+        let (result, _) = with_borrowed_account(4, |account| account.set_executable(true));
+        assert_eq!(
+            result,
+            Err(InstructionError::ExecutableAccountNotRentExempt)
+        );
+        let (result, _) = with_borrowed_account(0, |account| account.set_executable(true));
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_external_account_data_modified() {
+        let (result, _) = with_borrowed_account(1, |account| account.set_data_from_slice(&[3, 4]));
+        assert_eq!(result, Err(InstructionError::ExternalAccountDataModified));
+        let (result, _) = with_borrowed_account(1, |account| account.get_data_mut().map(|_| ()));
+        assert_eq!(result, Err(InstructionError::ExternalAccountDataModified));
+        // Only the owner may resize
+        let (result, _) = with_borrowed_account(1, |account| account.set_data_length(3));
+        assert_eq!(result, Err(InstructionError::AccountDataSizeChanged));
+    }
+
+    #[test]
+    fn test_readonly_data_modified() {
+        let (result, _) = with_borrowed_account(2, |account| account.set_data_from_slice(&[3, 4]));
+        assert_eq!(result, Err(InstructionError::ReadonlyDataModified));
+        let (result, _) = with_borrowed_account(2, |account| account.get_data_mut().map(|_| ()));
+        assert_eq!(result, Err(InstructionError::ReadonlyDataModified));
+    }
+
+    #[test]
+    fn test_account_borrow_outstanding() {
+        let (_, mut transaction_context) = with_borrowed_account(0, |_| ());
+        {
+            let instruction_context = transaction_context
+                .get_current_instruction_context()
+                .unwrap();
+            let _account = instruction_context
+                .try_borrow_instruction_account(&transaction_context, 0)
+                .unwrap();
+            assert!(matches!(
+                instruction_context.try_borrow_instruction_account(&transaction_context, 0),
+                Err(InstructionError::AccountBorrowFailed)
+            ));
+        }
+
+        // A program account still borrowed when the instruction returns
+        let accounts = Rc::clone(transaction_context.accounts());
+        let _program_account = accounts.get(0).unwrap().borrow_mut();
+        assert_eq!(
+            transaction_context.pop(),
+            Err(InstructionError::AccountBorrowOutstanding)
+        );
+    }
+
+    #[test]
+    fn test_touched_flags() {
+        let (_, transaction_context) = with_borrowed_account(0, |account| {
+            // Reads, no-op writes and rejected writes leave the account untouched
+            let _ = account.get_data();
+            account.set_lamports(EXEMPT_LAMPORTS).unwrap();
+            account.set_owner(&PROGRAM_ID).unwrap();
+            account.set_data_length(2).unwrap();
+            account.set_executable(false).unwrap();
+        });
+        assert_eq!(transaction_context.accounts().touched_count(), 0);
+
+        let (_, transaction_context) = with_borrowed_account(1, |account| {
+            assert!(account.checked_sub_lamports(1).is_err());
+            account.checked_add_lamports(1).unwrap();
+        });
+        assert_eq!(transaction_context.accounts().touched_count(), 1);
+        assert_eq!(
+            *transaction_context.accounts().touched_flags.borrow(),
+            [false, false, true, false, false, false].into()
+        );
+
+        let (_, transaction_context) =
+            with_borrowed_account(0, |account| account.get_data_mut().map(|_| ()).unwrap());
+        assert_eq!(
+            *transaction_context.accounts().touched_flags.borrow(),
+            [false, true, false, false, false, false].into()
+        );
+    }
+
+    #[test]
+    fn test_max_permitted_data_length() {
+        let (result, transaction_context) = with_borrowed_account(0, |account| {
+            account.set_data_length(MAX_PERMITTED_DATA_LENGTH as usize)?;
+            account.set_data_length(MAX_PERMITTED_DATA_LENGTH as usize + 1)
+        });
+        assert_eq!(result, Err(InstructionError::InvalidRealloc));
+        assert_eq!(
+            transaction_context.accounts_resize_delta(),
+            Ok(MAX_PERMITTED_DATA_LENGTH as i64 - 2)
+        );
+    }
+
+    #[test]
+    fn test_max_accounts_data_allocations_per_transaction() {
+        let (result, transaction_context) = with_borrowed_account(0, |account| {
+            *account
+                .transaction_context
+                .accounts_resize_delta
+                .borrow_mut() = MAX_PERMITTED_ACCOUNTS_DATA_ALLOCATIONS_PER_TRANSACTION - 10;
+            account.set_data_length(12)?;
+            account.extend_from_slice(&[0])
+        });
+        assert_eq!(
+            result,
+            Err(InstructionError::MaxAccountsDataAllocationsExceeded)
+        );
+        assert_eq!(
+            transaction_context.accounts_resize_delta(),
+            Ok(MAX_PERMITTED_ACCOUNTS_DATA_ALLOCATIONS_PER_TRANSACTION)
+        );
+
+        // Shrinking is always allowed
+        let (result, _) = with_borrowed_account(0, |account| {
+            *account
+                .transaction_context
+                .accounts_resize_delta
+                .borrow_mut() = MAX_PERMITTED_ACCOUNTS_DATA_ALLOCATIONS_PER_TRANSACTION;
+            account.set_data_length(1)
+        });
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_deconstruct() {
+        let (_, transaction_context) = with_borrowed_account(0, |_| ());
+        let accounts = Rc::clone(transaction_context.accounts());
+        assert_eq!(
+            transaction_context.deconstruct(),
+            Err(InstructionError::AccountBorrowOutstanding)
+        );
+        drop(accounts);
+
+        let (_, mut transaction_context) =
+            with_borrowed_account(0, |account| account.set_data_length(10).unwrap());
+        transaction_context.return_data = TransactionReturnData {
+            program_id: PROGRAM_ID,
+            data: vec![1, 2, 3],
+        };
+        let record = transaction_context.deconstruct().unwrap();
+        assert_eq!(record.accounts.len(), 6);
+        assert_eq!(record.accounts[1].0, [1; 32]);
+        assert_eq!(record.accounts[1].1.data(), &[0; 10]);
+        assert_eq!(record.touched_accounts, BTreeSet::from([[1; 32]]));
+        assert_eq!(record.return_data.program_id, PROGRAM_ID);
+        assert_eq!(record.return_data.data, vec![1, 2, 3]);
+        assert_eq!(record.accounts_resize_delta, 8);
+    }
 }
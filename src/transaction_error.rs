@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::InstructionError;
+
+pub type TransactionResult<T> = Result<T, TransactionError>;
+
+/// Reasons a transaction might be rejected.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub enum TransactionError {
+    /// An account is already being processed in another transaction in a way
+    /// that does not support parallelism
+    AccountInUse,
+
+    /// A `Pubkey` appears twice in the transaction's `account_keys`.  Instructions can reference
+    /// `Pubkey`s more than once but the message must contain a list with no duplicate keys
+    AccountLoadedTwice,
+
+    /// Attempt to debit an account but found no record of a prior credit.
+    AccountNotFound,
+
+    /// Attempt to load a program that does not exist
+    ProgramAccountNotFound,
+
+    /// The from `Pubkey` does not have sufficient balance to pay the fee to schedule the transaction
+    InsufficientFundsForFee,
+
+    /// This account may not be used to pay transaction fees
+    InvalidAccountForFee,
+
+    /// The bank has seen this transaction before. This can occur under normal operation
+    /// when a UDP packet is duplicated, as a user error from a client not updating
+    /// its `recent_blockhash`, or as a double-spend attack.
+    AlreadyProcessed,
+
+    /// The bank has not seen the given `recent_blockhash` or the transaction is too old and
+    /// the `recent_blockhash` has been discarded.
+    BlockhashNotFound,
+
+    /// An error occurred while processing an instruction. The first element of the tuple
+    /// indicates the instruction index in which the error occurred.
+    InstructionError(u8, InstructionError),
+
+    /// Loader call chain is too deep
+    CallChainTooDeep,
+
+    /// Transaction requires a fee but has no signature present
+    MissingSignatureForFee,
+
+    /// Transaction contains an invalid account reference
+    InvalidAccountIndex,
+
+    /// Transaction did not pass signature verification
+    SignatureFailure,
+
+    /// This program may not be used for executing instructions
+    InvalidProgramForExecution,
+
+    /// Transaction failed to sanitize accounts offsets correctly
+    /// implies that account locks are not taken for this TX, and should
+    /// not be unlocked.
+    SanitizeFailure,
+
+    ClusterMaintenance,
+
+    /// Transaction processing left an account with an outstanding borrowed reference
+    AccountBorrowOutstanding,
+
+    /// Transaction would exceed max Block Cost Limit
+    WouldExceedMaxBlockCostLimit,
+
+    /// Transaction version is unsupported
+    UnsupportedVersion,
+
+    /// Transaction loads a writable account that cannot be written
+    InvalidWritableAccount,
+
+    /// Transaction would exceed max account limit within the block
+    WouldExceedMaxAccountCostLimit,
+
+    /// Transaction would exceed account data limit within the block
+    WouldExceedAccountDataBlockLimit,
+
+    /// Transaction locked too many accounts
+    TooManyAccountLocks,
+
+    /// Address lookup table not found
+    AddressLookupTableNotFound,
+
+    /// Attempted to lookup addresses from an account owned by the wrong program
+    InvalidAddressLookupTableOwner,
+
+    /// Attempted to lookup addresses from an invalid account
+    InvalidAddressLookupTableData,
+
+    /// Address table lookup uses an invalid index
+    InvalidAddressLookupTableIndex,
+
+    /// Transaction leaves an account with a lower balance than rent-exempt minimum
+    InvalidRentPayingAccount,
+
+    /// Transaction would exceed max Vote Cost Limit
+    WouldExceedMaxVoteCostLimit,
+
+    /// Transaction would exceed total account data limit
+    WouldExceedAccountDataTotalLimit,
+
+    /// Transaction contains a duplicate instruction that is not allowed
+    DuplicateInstruction(u8),
+
+    /// Transaction results in an account with insufficient funds for rent
+    InsufficientFundsForRent {
+        account_index: u8,
+    },
+
+    /// Transaction exceeded max loaded accounts data size cap
+    MaxLoadedAccountsDataSizeExceeded,
+
+    /// LoadedAccountsDataSizeLimit set for transaction must be greater than 0.
+    InvalidLoadedAccountsDataSizeLimit,
+
+    /// Sanitized transaction differed before/after feature activiation. Needs to be resanitized.
+    ResanitizationNeeded,
+
+    /// Program execution is temporarily restricted on an account.
+    ProgramExecutionTemporarilyRestricted {
+        account_index: u8,
+    },
+
+    /// The total balance before the transaction does not equal the total balance after the transaction
+    UnbalancedTransaction,
+
+    /// Program cache hit max limit.
+    ProgramCacheHitMaxLimit,
+
+    /// Commit cancelled internally.
+    CommitCancelled,
+}
+
+impl std::error::Error for TransactionError {}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::AccountInUse => f.write_str("Account in use"),
+            Self::AccountLoadedTwice => f.write_str("Account loaded twice"),
+            Self::AccountNotFound => {
+                f.write_str("Attempt to debit an account but found no record of a prior credit.")
+            }
+            Self::ProgramAccountNotFound => {
+                f.write_str("Attempt to load a program that does not exist")
+            }
+            Self::InsufficientFundsForFee => f.write_str("Insufficient funds for fee"),
+            Self::InvalidAccountForFee => {
+                f.write_str("This account may not be used to pay transaction fees")
+            }
+            Self::AlreadyProcessed => f.write_str("This transaction has already been processed"),
+            Self::BlockhashNotFound => f.write_str("Blockhash not found"),
+            Self::InstructionError(idx, err) => {
+                write!(f, "Error processing Instruction {idx}: {err}")
+            }
+            Self::CallChainTooDeep => f.write_str("Loader call chain is too deep"),
+            Self::MissingSignatureForFee => {
+                f.write_str("Transaction requires a fee but has no signature present")
+            }
+            Self::InvalidAccountIndex => {
+                f.write_str("Transaction contains an invalid account reference")
+            }
+            Self::SignatureFailure => {
+                f.write_str("Transaction did not pass signature verification")
+            }
+            Self::InvalidProgramForExecution => {
+                f.write_str("This program may not be used for executing instructions")
+            }
+            Self::SanitizeFailure => {
+                f.write_str("Transaction failed to sanitize accounts offsets correctly")
+            }
+            Self::ClusterMaintenance => {
+                f.write_str("Transactions are currently disabled due to cluster maintenance")
+            }
+            Self::AccountBorrowOutstanding => f.write_str(
+                "Transaction processing left an account with an outstanding borrowed reference",
+            ),
+            Self::WouldExceedMaxBlockCostLimit => {
+                f.write_str("Transaction would exceed max Block Cost Limit")
+            }
+            Self::UnsupportedVersion => f.write_str("Transaction version is unsupported"),
+            Self::InvalidWritableAccount => {
+                f.write_str("Transaction loads a writable account that cannot be written")
+            }
+            Self::WouldExceedMaxAccountCostLimit => {
+                f.write_str("Transaction would exceed max account limit within the block")
+            }
+            Self::WouldExceedAccountDataBlockLimit => {
+                f.write_str("Transaction would exceed account data limit within the block")
+            }
+            Self::TooManyAccountLocks => f.write_str("Transaction locked too many accounts"),
+            Self::AddressLookupTableNotFound => {
+                f.write_str("Transaction loads an address table account that doesn't exist")
+            }
+            Self::InvalidAddressLookupTableOwner => {
+                f.write_str("Transaction loads an address table account with an invalid owner")
+            }
+            Self::InvalidAddressLookupTableData => {
+                f.write_str("Transaction loads an address table account with invalid data")
+            }
+            Self::InvalidAddressLookupTableIndex => {
+                f.write_str("Transaction address table lookup uses an invalid index")
+            }
+            Self::InvalidRentPayingAccount => f.write_str(
+                "Transaction leaves an account with a lower balance than rent-exempt minimum",
+            ),
+            Self::WouldExceedMaxVoteCostLimit => {
+                f.write_str("Transaction would exceed max Vote Cost Limit")
+            }
+            Self::WouldExceedAccountDataTotalLimit => {
+                f.write_str("Transaction would exceed total account data limit")
+            }
+            Self::DuplicateInstruction(idx) => {
+                write!(
+                    f,
+                    "Transaction contains a duplicate instruction ({idx}) that is not allowed"
+                )
+            }
+            Self::InsufficientFundsForRent { account_index } => {
+                write!(
+                    f,
+                    "Transaction results in an account ({account_index}) with insufficient funds for rent"
+                )
+            }
+            Self::MaxLoadedAccountsDataSizeExceeded => {
+                f.write_str("Transaction exceeded max loaded accounts data size cap")
+            }
+            Self::InvalidLoadedAccountsDataSizeLimit => f.write_str(
+                "LoadedAccountsDataSizeLimit set for transaction must be greater than 0.",
+            ),
+            Self::ResanitizationNeeded => f.write_str("ResanitizationNeeded"),
+            Self::ProgramExecutionTemporarilyRestricted { account_index } => {
+                write!(
+                    f,
+                    "Execution of the program referenced by account at index {account_index} is \
+                     temporarily restricted."
+                )
+            }
+            Self::UnbalancedTransaction => {
+                f.write_str("Sum of account balances before and after transaction do not match")
+            }
+            Self::ProgramCacheHitMaxLimit => f.write_str("Program cache hit max limit"),
+            Self::CommitCancelled => f.write_str("CommitCancelled"),
+        }
+    }
+}